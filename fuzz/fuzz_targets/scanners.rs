@@ -0,0 +1,35 @@
+//! Runs the text scanners xml-pretty uses on source and formatted markup over arbitrary text.
+//! They must not panic, and reindenting may change nothing but the whitespace that starts a
+//! line.
+
+#![no_main]
+
+#[path = "../../src/markup.rs"]
+#[allow(dead_code)]
+mod markup;
+#[path = "../../src/reindent.rs"]
+#[allow(dead_code)]
+mod reindent;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    let _ = markup::root_element_offset(text);
+    let _ = markup::is_prolog_only(text);
+    let _ = markup::trailing_content(text);
+    let _ = markup::tag_len(text);
+    assert_eq!(
+        markup::map_attribute_values(text, |value, _| value.to_string()),
+        text
+    );
+
+    for unit in ["", "  ", "\t"] {
+        let (reindented, _) = reindent::reindent(text, unit);
+        let lines = |s: &str| {
+            s.split('\n')
+                .map(|line| line.trim_start_matches([' ', '\t']).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(lines(&reindented), lines(text));
+    }
+});
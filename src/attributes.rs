@@ -0,0 +1,466 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use xmlem::{Document, Element};
+
+use crate::outline::{element_path, write_location, Locator};
+
+/// How namespace declarations are ordered when attributes are sorted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum XmlnsSort {
+    /// By the declared prefix, e.g. `xmlns:a` before `xmlns:b`.
+    #[default]
+    Prefix,
+    /// By the namespace URI, so related schema versions end up next to each other.
+    Uri,
+    /// In the order they appear in the input.
+    None,
+}
+
+impl FromStr for XmlnsSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "prefix" => Ok(XmlnsSort::Prefix),
+            "uri" => Ok(XmlnsSort::Uri),
+            "none" => Ok(XmlnsSort::None),
+            other => Err(format!(
+                "expected 'prefix', 'uri' or 'none', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// Elements whose attributes keep their input order when the rest are sorted, given as a list
+/// of element names. A name ending in `/**` also covers everything inside that element.
+#[derive(Debug, Default)]
+pub struct SortExemptions {
+    elements: Vec<String>,
+    subtrees: Vec<String>,
+}
+
+impl SortExemptions {
+    pub fn new<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut exemptions = SortExemptions::default();
+        for name in names {
+            match name.strip_suffix("/**") {
+                Some(name) => exemptions.subtrees.push(name.to_string()),
+                None => exemptions.elements.push(name.to_string()),
+            }
+        }
+        exemptions
+    }
+
+    fn is_exempt(&self, doc: &Document, element: Element) -> bool {
+        if self.elements.iter().any(|name| name == element.name(doc)) {
+            return true;
+        }
+
+        let mut current = Some(element);
+        while let Some(element) = current {
+            if self.subtrees.iter().any(|name| name == element.name(doc)) {
+                return true;
+            }
+            current = element.parent(doc);
+        }
+        false
+    }
+}
+
+fn is_namespace_declaration(name: &str) -> bool {
+    name == "xmlns" || name.starts_with("xmlns:")
+}
+
+/// The order `sort_attributes` puts `(name, value)` attribute pairs in.
+fn compare_attributes(
+    (a_name, a_value): &(String, String),
+    (b_name, b_value): &(String, String),
+    xmlns_sort: XmlnsSort,
+) -> Ordering {
+    let a_is_xmlns = is_namespace_declaration(a_name);
+    let b_is_xmlns = is_namespace_declaration(b_name);
+
+    match (a_is_xmlns, b_is_xmlns) {
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a_name.cmp(b_name),
+        (true, true) => {
+            (a_name != "xmlns")
+                .cmp(&(b_name != "xmlns"))
+                .then_with(|| match xmlns_sort {
+                    XmlnsSort::Prefix => a_name.cmp(b_name),
+                    XmlnsSort::Uri => a_value.cmp(b_value).then_with(|| a_name.cmp(b_name)),
+                    XmlnsSort::None => Ordering::Equal,
+                })
+        }
+    }
+}
+
+/// Sorts the attributes of every element in `doc`. Namespace declarations come first, with the
+/// default `xmlns` declaration ahead of any prefixed ones and the rest ordered by `xmlns_sort`,
+/// followed by all other attributes ordered by name. Names and values are compared by code
+/// point, never by locale. Elements covered by `exemptions` are left as they are.
+pub fn sort_attributes(doc: &mut Document, xmlns_sort: XmlnsSort, exemptions: &SortExemptions) {
+    let root = doc.root();
+    let elements = std::iter::once(root)
+        .chain(root.walk(doc))
+        .filter(|element| !exemptions.is_exempt(doc, *element))
+        .collect::<Vec<Element>>();
+
+    for element in elements {
+        let mut attrs = element
+            .attributes(doc)
+            .iter()
+            .map(|(name, value)| (name.prefixed_name().to_string(), value.clone()))
+            .collect::<Vec<_>>();
+
+        attrs.sort_by(|a, b| compare_attributes(a, b, xmlns_sort));
+
+        for (name, _) in attrs.iter() {
+            element.remove_attribute(doc, name);
+        }
+
+        for (name, value) in attrs.iter() {
+            element.set_attribute(doc, name.as_str(), value);
+        }
+    }
+}
+
+/// An element whose attributes are not in the order `sort_attributes` would give them.
+#[derive(Debug)]
+pub struct UnsortedAttribute {
+    pub path: String,
+    /// The first attribute that should come earlier than it does.
+    pub name: String,
+    /// Where that attribute is in the source.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for UnsortedAttribute {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_location(f, self.location)?;
+        write!(
+            f,
+            "attribute '{}' of {} is out of order",
+            self.name, self.path
+        )
+    }
+}
+
+/// Finds the elements of `doc` whose attributes `sort_attributes` would reorder, without
+/// changing anything.
+pub fn check_sorted(
+    doc: &Document,
+    xmlns_sort: XmlnsSort,
+    exemptions: &SortExemptions,
+    locator: &Locator,
+) -> Vec<UnsortedAttribute> {
+    let root = doc.root();
+    std::iter::once(root)
+        .chain(root.walk(doc))
+        .filter(|element| !exemptions.is_exempt(doc, *element))
+        .filter_map(|element| {
+            let attrs = element
+                .attributes(doc)
+                .iter()
+                .map(|(name, value)| (name.prefixed_name().to_string(), value.clone()))
+                .collect::<Vec<_>>();
+
+            attrs
+                .windows(2)
+                .find(|pair| compare_attributes(&pair[0], &pair[1], xmlns_sort).is_gt())
+                .map(|pair| UnsortedAttribute {
+                    path: element_path(doc, element),
+                    name: pair[1].0.clone(),
+                    location: locator.attribute(element, &pair[1].0),
+                })
+        })
+        .collect()
+}
+
+/// Whether `name` matches `pattern`, which is either an exact attribute name or a prefix
+/// followed by `*`, as in `data-*`.
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// Removes every attribute whose name, as written, matches one of `patterns` from every element
+/// in `doc`. Namespace declarations are always kept, since the document may depend on them.
+pub fn strip_attributes(doc: &mut Document, patterns: &[String]) {
+    let root = doc.root();
+    let elements = std::iter::once(root)
+        .chain(root.walk(doc))
+        .collect::<Vec<Element>>();
+
+    for element in elements {
+        let stripped = element
+            .attributes(doc)
+            .keys()
+            .map(|name| name.prefixed_name().to_string())
+            .filter(|name| !is_namespace_declaration(name))
+            .filter(|name| {
+                patterns
+                    .iter()
+                    .any(|pattern| matches_pattern(name, pattern))
+            })
+            .collect::<Vec<_>>();
+
+        for name in stripped {
+            element.remove_attribute(doc, &name);
+        }
+    }
+}
+
+/// Rewrites each empty attribute named in `names`, such as `disabled=""`, to repeat its name
+/// (`disabled="disabled"`), the XHTML form of a boolean attribute. Attributes not listed are
+/// left alone, since an empty value is often meaningful.
+pub fn expand_boolean_attributes(doc: &mut Document, names: &[String]) {
+    let root = doc.root();
+    let elements = std::iter::once(root)
+        .chain(root.walk(doc))
+        .collect::<Vec<Element>>();
+
+    for element in elements {
+        let expanded = element
+            .attributes(doc)
+            .iter()
+            .filter(|(name, value)| {
+                value.is_empty() && names.iter().any(|n| n == name.prefixed_name())
+            })
+            .map(|(name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        for name in expanded {
+            let value = name.prefixed_name().to_string();
+            element.set_attribute(doc, name, &value);
+        }
+    }
+}
+
+/// The case an attribute value is normalized to by `--normalize-attr-case`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Case {
+    Lower,
+    Upper,
+}
+
+impl Case {
+    fn apply(self, value: &str) -> String {
+        match self {
+            Case::Lower => value.to_lowercase(),
+            Case::Upper => value.to_uppercase(),
+        }
+    }
+}
+
+/// `NAME=lower` or `NAME=upper`: the values of attribute `NAME`, as written including any
+/// prefix, are case-normalized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaseRule {
+    pub name: String,
+    pub case: Case,
+}
+
+impl FromStr for CaseRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, case) = match s.rsplit_once('=') {
+            Some((name, "lower")) if !name.is_empty() => (name, Case::Lower),
+            Some((name, "upper")) if !name.is_empty() => (name, Case::Upper),
+            _ => {
+                return Err(format!(
+                    "expected 'NAME=lower' or 'NAME=upper', got '{}'",
+                    s
+                ))
+            }
+        };
+        Ok(CaseRule {
+            name: name.to_string(),
+            case,
+        })
+    }
+}
+
+/// An attribute value that differs from the case its `CaseRule` asks for.
+#[derive(Debug)]
+pub struct UnnormalizedCase {
+    pub path: String,
+    pub name: String,
+    pub value: String,
+    pub expected: String,
+    /// Where the attribute is in the source.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for UnnormalizedCase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_location(f, self.location)?;
+        write!(
+            f,
+            "attribute '{}' of {} is \"{}\", expected \"{}\"",
+            self.name, self.path, self.value, self.expected
+        )
+    }
+}
+
+/// Finds the attribute values that `normalize_case` would change, without changing anything.
+pub fn check_case(doc: &Document, rules: &[CaseRule], locator: &Locator) -> Vec<UnnormalizedCase> {
+    let root = doc.root();
+    let mut found = vec![];
+
+    for element in std::iter::once(root).chain(root.walk(doc)) {
+        for (name, value) in element.attributes(doc) {
+            let Some(rule) = rules.iter().find(|rule| rule.name == name.prefixed_name()) else {
+                continue;
+            };
+            let expected = rule.case.apply(value);
+            if expected != *value {
+                found.push(UnnormalizedCase {
+                    path: element_path(doc, element),
+                    name: rule.name.clone(),
+                    value: value.clone(),
+                    expected,
+                    location: locator.attribute(element, &rule.name),
+                });
+            }
+        }
+    }
+
+    found
+}
+
+/// Lower- or upper-cases the values of the attributes named by `rules`. Attribute values are
+/// usually case-sensitive data, so nothing else is touched.
+pub fn normalize_case(doc: &mut Document, rules: &[CaseRule]) {
+    let root = doc.root();
+    let elements = std::iter::once(root)
+        .chain(root.walk(doc))
+        .collect::<Vec<Element>>();
+
+    for element in elements {
+        let normalized = element
+            .attributes(doc)
+            .iter()
+            .filter_map(|(name, value)| {
+                let rule = rules
+                    .iter()
+                    .find(|rule| rule.name == name.prefixed_name())?;
+                Some((name.clone(), rule.case.apply(value)))
+            })
+            .collect::<Vec<_>>();
+
+        for (name, value) in normalized {
+            element.set_attribute(doc, name, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(xml: &str, xmlns_sort: XmlnsSort, exemptions: &[&str]) -> String {
+        let mut doc = Document::from_str(xml).unwrap();
+        sort_attributes(
+            &mut doc,
+            xmlns_sort,
+            &SortExemptions::new(exemptions.iter().copied()),
+        );
+        doc.to_string()
+    }
+
+    const DECLARATIONS: &str =
+        r#"<r z="1" xmlns:b="urn:a" a="2" xmlns="urn:default" xmlns:a="urn:c" xmlns:c="urn:b"/>"#;
+
+    #[test]
+    fn sorts_declarations_by_prefix() {
+        assert_eq!(
+            sorted(DECLARATIONS, XmlnsSort::Prefix, &[]),
+            r#"<r xmlns="urn:default" xmlns:a="urn:c" xmlns:b="urn:a" xmlns:c="urn:b" a="2" z="1"/>"#
+        );
+    }
+
+    #[test]
+    fn sorts_declarations_by_uri() {
+        assert_eq!(
+            sorted(DECLARATIONS, XmlnsSort::Uri, &[]),
+            r#"<r xmlns="urn:default" xmlns:b="urn:a" xmlns:c="urn:b" xmlns:a="urn:c" a="2" z="1"/>"#
+        );
+        // Prefixes break ties between declarations of the same namespace.
+        assert_eq!(
+            sorted(
+                r#"<r xmlns:y="urn:x" xmlns:x="urn:x"/>"#,
+                XmlnsSort::Uri,
+                &[]
+            ),
+            r#"<r xmlns:x="urn:x" xmlns:y="urn:x"/>"#
+        );
+    }
+
+    #[test]
+    fn keeps_declarations_in_input_order_with_none() {
+        assert_eq!(
+            sorted(DECLARATIONS, XmlnsSort::None, &[]),
+            r#"<r xmlns="urn:default" xmlns:b="urn:a" xmlns:a="urn:c" xmlns:c="urn:b" a="2" z="1"/>"#
+        );
+    }
+
+    #[test]
+    fn exempts_named_elements_only() {
+        assert_eq!(
+            sorted(
+                r#"<r b="" a=""><keep b="" a=""><c b="" a=""/></keep></r>"#,
+                XmlnsSort::Prefix,
+                &["keep"]
+            ),
+            r#"<r a="" b=""><keep b="" a=""><c a="" b=""/></keep></r>"#
+        );
+    }
+
+    #[test]
+    fn exempts_subtrees_with_double_star() {
+        assert_eq!(
+            sorted(
+                r#"<r b="" a=""><keep b="" a=""><c b="" a=""/></keep><c b="" a=""/></r>"#,
+                XmlnsSort::Prefix,
+                &["keep/**"]
+            ),
+            r#"<r a="" b=""><keep b="" a=""><c b="" a=""/></keep><c a="" b=""/></r>"#
+        );
+    }
+
+    #[test]
+    fn check_sorted_skips_exempt_elements() {
+        let source = r#"<r b="" a=""><keep b="" a=""><c b="" a=""/></keep></r>"#;
+        let doc = Document::from_str(source).unwrap();
+        let locator = Locator::new(&doc, source);
+        let unsorted = |exemptions: &[&str]| {
+            check_sorted(
+                &doc,
+                XmlnsSort::Prefix,
+                &SortExemptions::new(exemptions.iter().copied()),
+                &locator,
+            )
+            .iter()
+            .map(|unsorted| unsorted.path.clone())
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(unsorted(&[]), ["/r", "/r/keep", "/r/keep/c"]);
+        assert_eq!(unsorted(&["keep"]), ["/r", "/r/keep/c"]);
+        assert_eq!(unsorted(&["keep/**"]), ["/r"]);
+    }
+
+    #[test]
+    fn parses_xmlns_sort() {
+        assert_eq!("uri".parse(), Ok(XmlnsSort::Uri));
+        assert_eq!(
+            "url".parse::<XmlnsSort>(),
+            Err("expected 'prefix', 'uri' or 'none', got 'url'".to_string())
+        );
+    }
+}
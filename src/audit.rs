@@ -0,0 +1,152 @@
+use std::{
+    fmt::Write as _,
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+/// The `--log-file` record of a run: a header line describing the run, then one JSON line per
+/// document, each written as soon as the document is done so an interrupted run still leaves
+/// a record of what it changed.
+pub struct AuditLog {
+    file: File,
+}
+
+/// What happened to one document, for its line in the log.
+pub struct Entry<'a> {
+    pub path: Option<&'a str>,
+    /// `rewritten`, `unchanged`, `printed`, `checked`, `unformatted` (a document `--lint`
+    /// found not to be formatted), `skipped` or `error`.
+    pub action: &'static str,
+    pub bytes_before: Option<u64>,
+    /// The size and CRC-32 of the file written, if any.
+    pub written: Option<(u64, u32)>,
+    pub error: Option<String>,
+    /// What kinds of difference an `unformatted` document has, as [`crate::lint::classify`]
+    /// names them.
+    pub categories: &'a [&'static str],
+    /// The line and column an error was found at, when it is known.
+    pub location: Option<(usize, usize)>,
+    /// Time spent on the document as [`crate::timings::Timings::to_json`] gave it, unless it was never run.
+    pub timings: Option<String>,
+}
+
+impl AuditLog {
+    /// Opens `path` for appending and writes the header line. `settings` is hashed so runs with
+    /// the same effective settings can be recognized.
+    pub fn open(path: &Path, settings: &str) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let mut log = AuditLog { file };
+        log.write_line(&format!(
+            "{{\"timestamp\":{},\"tool\":\"xml-pretty\",\"version\":{},\"settings_crc32\":\"{:08x}\"}}",
+            json_string(&timestamp()),
+            json_string(env!("CARGO_PKG_VERSION")),
+            crc32(settings.as_bytes())
+        ))?;
+        Ok(log)
+    }
+
+    pub fn record(&mut self, entry: &Entry) -> io::Result<()> {
+        let mut line = format!(
+            "{{\"timestamp\":{},\"path\":{},\"action\":{},\"bytes_before\":{}",
+            json_string(&timestamp()),
+            entry.path.map_or("null".to_string(), json_string),
+            json_string(entry.action),
+            entry
+                .bytes_before
+                .map_or("null".to_string(), |n| n.to_string())
+        );
+        match entry.written {
+            Some((size, crc)) => {
+                let _ = write!(line, ",\"bytes_after\":{},\"crc32\":\"{:08x}\"", size, crc);
+            }
+            None => line.push_str(",\"bytes_after\":null,\"crc32\":null"),
+        }
+        if let Some(error) = entry.error.as_deref() {
+            let _ = write!(line, ",\"error\":{}", json_string(error));
+        }
+        if !entry.categories.is_empty() {
+            let _ = write!(line, ",\"categories\":{}", json_strings(entry.categories));
+        }
+        if let Some((line_number, column)) = entry.location {
+            let _ = write!(line, ",\"line\":{},\"column\":{}", line_number, column);
+        }
+        if let Some(timings) = entry.timings.as_deref() {
+            let _ = write!(line, ",\"timings\":{}", timings);
+        }
+        line.push('}');
+        self.write_line(&line)
+    }
+
+    /// Writes `line` with a single call, so each line lands whole.
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.file.write_all(format!("{}\n", line).as_bytes())
+    }
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(bytes);
+    crc.sum()
+}
+
+pub fn json_string(s: &str) -> String {
+    let mut output = String::with_capacity(s.len() + 2);
+    output.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(output, "\\u{:04x}", c as u32);
+            }
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+/// `values` as a JSON array of strings.
+pub fn json_strings(values: &[&str]) -> String {
+    let values = values
+        .iter()
+        .map(|value| json_string(value))
+        .collect::<Vec<_>>();
+    format!("[{}]", values.join(","))
+}
+
+/// The current time in UTC as RFC 3339, e.g. `2024-05-01T12:30:00.250Z`.
+fn timestamp() -> String {
+    let elapsed = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = elapsed.as_secs();
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // Converts days since 1970-01-01 to a civil date in the proleptic Gregorian calendar.
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60,
+        elapsed.subsec_millis()
+    )
+}
@@ -0,0 +1,280 @@
+use std::{borrow::Cow, fmt, str::FromStr};
+
+/// What to do with characters that the XML `Char` production does not allow, such as control
+/// characters, which scraped documents sometimes contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InvalidCharPolicy {
+    /// Refuse the document, giving the position of the first one.
+    #[default]
+    Error,
+    /// Remove them, together with any stray byte order marks.
+    Strip,
+    /// Keep them, to be written as character references such as `&#x0001;`. The output then
+    /// records what was there, but is still not well-formed XML 1.0.
+    Entity,
+}
+
+impl FromStr for InvalidCharPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(InvalidCharPolicy::Error),
+            "strip" => Ok(InvalidCharPolicy::Strip),
+            "entity" => Ok(InvalidCharPolicy::Entity),
+            other => Err(format!(
+                "expected 'error', 'strip' or 'entity', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// A character found in the source text, with its position.
+#[derive(Debug, Clone, Copy)]
+pub struct Found {
+    pub ch: char,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Found {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "U+{:04X} at line {}, column {}",
+            self.ch as u32, self.line, self.column
+        )
+    }
+}
+
+/// A character that is not allowed in XML, refused under [`InvalidCharPolicy::Error`].
+#[derive(Debug)]
+pub struct InvalidChar(pub Found);
+
+impl fmt::Display for InvalidChar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid character {} (use --invalid-chars strip or entity to accept it)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for InvalidChar {}
+
+/// The first of a kind of character found in a document, and how many there are in all.
+#[derive(Debug, Clone, Copy)]
+pub struct Occurrences {
+    pub first: Found,
+    pub count: usize,
+}
+
+/// What [`check`] found in a document, to be reported as warnings.
+#[derive(Debug, Default)]
+pub struct Findings {
+    /// Characters the XML `Char` production does not allow.
+    pub invalid: Option<Occurrences>,
+    /// Byte order marks after the start of the document, usually left by concatenating files.
+    /// XML allows U+FEFF anywhere, but in the middle of a document it is almost never wanted.
+    pub stray_boms: Option<Occurrences>,
+}
+
+/// Whether the XML 1.0 `Char` production allows `ch`. Surrogates cannot occur in a `str`.
+fn is_xml_char(ch: char) -> bool {
+    matches!(ch, '\t' | '\n' | '\r' | '\u{20}'..='\u{fffd}' | '\u{10000}'..)
+}
+
+/// Looks for invalid characters and stray byte order marks in the source text `s` and applies
+/// `policy` to them. Returns the text to parse, which only differs from `s` under
+/// [`InvalidCharPolicy::Strip`], and what was found.
+pub fn check(s: &str, policy: InvalidCharPolicy) -> Result<(Cow<'_, str>, Findings), InvalidChar> {
+    let mut findings = Findings::default();
+    let mut stripped = String::new();
+    let mut kept_until = 0;
+    let (mut line, mut column) = (1, 1);
+
+    for (offset, ch) in s.char_indices() {
+        let found = Found { ch, line, column };
+        if ch == '\n' {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+
+        let occurrences = if !is_xml_char(ch) {
+            if policy == InvalidCharPolicy::Error {
+                return Err(InvalidChar(found));
+            }
+            &mut findings.invalid
+        } else if ch == '\u{feff}' && offset > 0 {
+            &mut findings.stray_boms
+        } else {
+            continue;
+        };
+        match occurrences {
+            Some(occurrences) => occurrences.count += 1,
+            None => {
+                *occurrences = Some(Occurrences {
+                    first: found,
+                    count: 1,
+                })
+            }
+        }
+
+        if policy == InvalidCharPolicy::Strip {
+            stripped.push_str(&s[kept_until..offset]);
+            kept_until = offset + ch.len_utf8();
+        }
+    }
+
+    if kept_until == 0 {
+        return Ok((Cow::Borrowed(s), findings));
+    }
+    stripped.push_str(&s[kept_until..]);
+    Ok((Cow::Owned(stripped), findings))
+}
+
+impl Findings {
+    /// Describes what was found and done under `policy`, one warning per kind of character.
+    pub fn warnings(&self, policy: InvalidCharPolicy) -> Vec<String> {
+        let is_strip = policy == InvalidCharPolicy::Strip;
+        let mut warnings = vec![];
+
+        if let Some(Occurrences { first, count }) = self.invalid {
+            warnings.push(format!(
+                "{} {} invalid character{}, the first {}{}",
+                if is_strip { "removed" } else { "kept" },
+                count,
+                if count == 1 { "" } else { "s" },
+                first,
+                if is_strip {
+                    ""
+                } else {
+                    "; the output is not well-formed"
+                }
+            ));
+        }
+
+        if let Some(Occurrences { first, count }) = self.stray_boms {
+            warnings.push(format!(
+                "{} {} byte order mark{} after the start of the document, the first at line {}, column {}",
+                if is_strip { "removed" } else { "found" },
+                count,
+                if count == 1 { "" } else { "s" },
+                first.line,
+                first.column
+            ));
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "<r>a\u{1}b\n<s>\u{feff}\u{0}</s>\u{feff}</r>";
+
+    /// The text to parse, whether it was borrowed, and the warnings.
+    fn checked(s: &str, policy: InvalidCharPolicy) -> (String, bool, Vec<String>) {
+        let (text, findings) = check(s, policy).unwrap();
+        let is_borrowed = matches!(text, Cow::Borrowed(_));
+        (text.into_owned(), is_borrowed, findings.warnings(policy))
+    }
+
+    #[test]
+    fn error_refuses_the_first_invalid_character() {
+        let error = check(SOURCE, InvalidCharPolicy::Error).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid character U+0001 at line 1, column 5 (use --invalid-chars strip or entity to accept it)"
+        );
+    }
+
+    #[test]
+    fn strip_removes_invalid_characters_and_stray_boms() {
+        assert_eq!(
+            checked(SOURCE, InvalidCharPolicy::Strip),
+            (
+                "<r>ab\n<s></s></r>".to_string(),
+                false,
+                vec![
+                    "removed 2 invalid characters, the first U+0001 at line 1, column 5"
+                        .to_string(),
+                    "removed 2 byte order marks after the start of the document, the first at line 2, column 4"
+                        .to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn entity_keeps_the_text_as_it_is() {
+        assert_eq!(
+            checked(SOURCE, InvalidCharPolicy::Entity),
+            (
+                SOURCE.to_string(),
+                true,
+                vec![
+                    "kept 2 invalid characters, the first U+0001 at line 1, column 5; the output is not well-formed"
+                        .to_string(),
+                    "found 2 byte order marks after the start of the document, the first at line 2, column 4"
+                        .to_string(),
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn stray_boms_are_reported_under_every_policy() {
+        let source = "\u{feff}<r>\u{feff}</r>";
+        let warning =
+            "byte order mark after the start of the document, the first at line 1, column 5";
+        for policy in [InvalidCharPolicy::Error, InvalidCharPolicy::Entity] {
+            assert_eq!(
+                checked(source, policy),
+                (
+                    source.to_string(),
+                    true,
+                    vec![format!("found 1 {}", warning)]
+                )
+            );
+        }
+        // The leading one is a real byte order mark, and stays.
+        assert_eq!(
+            checked(source, InvalidCharPolicy::Strip),
+            (
+                "\u{feff}<r></r>".to_string(),
+                false,
+                vec![format!("removed 1 {}", warning)]
+            )
+        );
+    }
+
+    #[test]
+    fn clean_text_is_borrowed_without_warnings() {
+        let source = "<r a=\"\t\">\u{10000}\r\n</r>";
+        for policy in [
+            InvalidCharPolicy::Error,
+            InvalidCharPolicy::Strip,
+            InvalidCharPolicy::Entity,
+        ] {
+            assert_eq!(checked(source, policy), (source.to_string(), true, vec![]));
+        }
+    }
+
+    #[test]
+    fn policies_parse() {
+        assert_eq!("error".parse(), Ok(InvalidCharPolicy::Error));
+        assert_eq!("strip".parse(), Ok(InvalidCharPolicy::Strip));
+        assert_eq!("entity".parse(), Ok(InvalidCharPolicy::Entity));
+        assert_eq!(
+            "keep".parse::<InvalidCharPolicy>(),
+            Err("expected 'error', 'strip' or 'entity', got 'keep'".to_string())
+        );
+    }
+}
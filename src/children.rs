@@ -0,0 +1,345 @@
+use std::str::FromStr;
+
+use xmlem::{Document, Element, Node, Selector};
+
+use crate::outline::{element_path, write_location, Locator};
+
+/// What the children of an element are ordered by when sorted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SortKey {
+    /// The element name as written, including any prefix.
+    TagName,
+    /// The value of an attribute. Children without it go last.
+    Attribute(String),
+    /// The text of the named child elements, compared in turn, as in `key=groupId/artifactId`
+    /// for a pom's `<dependency>` elements. Children missing any of them go last.
+    ChildText {
+        names: Vec<String>,
+        is_case_insensitive: bool,
+    },
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(key) = s.strip_prefix("key=") {
+            let (key, is_case_insensitive) = match key.strip_suffix(":icase") {
+                Some(key) => (key, true),
+                None => (key, false),
+            };
+            let names = key.split('/').map(str::to_string).collect::<Vec<_>>();
+            if names.iter().all(|name| !name.is_empty()) {
+                return Ok(SortKey::ChildText {
+                    names,
+                    is_case_insensitive,
+                });
+            }
+        }
+
+        match s {
+            "name" => Ok(SortKey::TagName),
+            s => match s.strip_prefix('@') {
+                Some(name) if !name.is_empty() => Ok(SortKey::Attribute(name.to_string())),
+                _ => Err(format!(
+                    "expected 'name', '@ATTRIBUTE' or 'key=CHILD[/CHILD...][:icase]', got '{}'",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// A CSS selector for the elements whose children are sorted, parsed along with the other
+/// command line arguments.
+#[derive(Debug, Clone)]
+pub struct Parents(pub Selector);
+
+impl FromStr for Parents {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Selector::new(s)
+            .map(Parents)
+            .map_err(|_| format!("invalid selector '{}'", s))
+    }
+}
+
+/// The trimmed text of the first child of `element` named `name`, if there is one.
+fn child_text(doc: &Document, element: Element, name: &str) -> Option<String> {
+    let child = element
+        .children(doc)
+        .into_iter()
+        .find(|child| child.name(doc) == name)?;
+
+    let text = child
+        .child_nodes(doc)
+        .iter()
+        .filter_map(|node| match node {
+            Node::Text(text) => Some(text.as_str(doc)),
+            Node::CDataSection(cdata) => Some(cdata.as_str(doc)),
+            _ => None,
+        })
+        .collect::<String>();
+    Some(text.trim().to_string())
+}
+
+/// What `element` is ordered by; children without the attribute or key elements sort after all
+/// others.
+fn sort_key(doc: &Document, element: Element, by: &SortKey) -> (bool, Vec<String>) {
+    match by {
+        SortKey::TagName => (false, vec![element.qname(doc).prefixed_name().to_string()]),
+        SortKey::Attribute(name) => match element.attribute(doc, name) {
+            Some(value) => (false, vec![value.to_string()]),
+            None => (true, vec![]),
+        },
+        SortKey::ChildText {
+            names,
+            is_case_insensitive,
+        } => {
+            let texts = names
+                .iter()
+                .map(|name| child_text(doc, element, name))
+                .collect::<Option<Vec<_>>>();
+            let Some(texts) = texts else {
+                return (true, vec![]);
+            };
+            let texts = texts
+                .into_iter()
+                .map(|text| {
+                    if *is_case_insensitive {
+                        text.to_lowercase()
+                    } else {
+                        text
+                    }
+                })
+                .collect();
+            (false, texts)
+        }
+    }
+}
+
+/// A child element together with the comments directly before it, which move with it.
+struct Entry {
+    comments: Vec<String>,
+    element: Element,
+}
+
+/// Sorts the child elements of every element matching `parents` by `by`, keeping the original
+/// order between children with equal keys. Comments stay with the element that follows them.
+/// Elements with text or CDATA among their children are order-sensitive and are left alone;
+/// their names are returned so they can be reported.
+pub fn sort_children(doc: &mut Document, parents: &Parents, by: &SortKey) -> Vec<String> {
+    let root = doc.root();
+    let parents = std::iter::once(root)
+        .chain(root.walk(doc))
+        .filter(|element| parents.0.matches(doc, *element))
+        .collect::<Vec<Element>>();
+
+    let mut skipped = vec![];
+
+    for parent in parents {
+        let mut entries = vec![];
+        let mut comments = vec![];
+        let mut is_mixed = false;
+
+        for node in parent.child_nodes(doc) {
+            match node {
+                Node::Element(element) => entries.push(Entry {
+                    comments: std::mem::take(&mut comments),
+                    element: *element,
+                }),
+                Node::Comment(comment) => comments.push(comment.as_str(doc).to_string()),
+                _ => is_mixed = true,
+            }
+        }
+
+        if is_mixed {
+            skipped.push(parent.name(doc).to_string());
+            continue;
+        }
+
+        entries.sort_by_cached_key(|entry| sort_key(doc, entry.element, by));
+
+        let old_comments = parent
+            .child_nodes(doc)
+            .iter()
+            .filter(|node| matches!(node, Node::Comment(_)))
+            .copied()
+            .collect::<Vec<_>>();
+        for comment in old_comments {
+            parent.remove_child(doc, comment);
+        }
+
+        for entry in entries {
+            for comment in entry.comments {
+                parent.append_comment(doc, &comment);
+            }
+            parent.append_element(doc, entry.element);
+        }
+        for comment in comments {
+            parent.append_comment(doc, &comment);
+        }
+    }
+
+    skipped
+}
+
+/// An element matched by `--check-sorted-children` whose children are out of order.
+#[derive(Debug)]
+pub struct UnsortedChild {
+    pub path: String,
+    /// The first child that should come earlier than it does, as `name` or `name key="value"`.
+    pub child: String,
+    /// Where that child is in the source.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for UnsortedChild {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_location(f, self.location)?;
+        write!(f, "child <{}> of {} is out of order", self.child, self.path)
+    }
+}
+
+/// Finds the elements matching `parents` whose children `sort_children` would reorder, without
+/// changing anything. Mixed content is skipped, as it is by `sort_children`.
+pub fn check_sorted(
+    doc: &Document,
+    parents: &Parents,
+    by: &SortKey,
+    locator: &Locator,
+) -> Vec<UnsortedChild> {
+    let root = doc.root();
+    std::iter::once(root)
+        .chain(root.walk(doc))
+        .filter(|element| parents.0.matches(doc, *element))
+        .filter(|parent| {
+            parent
+                .child_nodes(doc)
+                .iter()
+                .all(|node| matches!(node, Node::Element(_) | Node::Comment(_)))
+        })
+        .filter_map(|parent| {
+            let children = parent.children(doc);
+            let unsorted = children
+                .windows(2)
+                .find(|pair| sort_key(doc, pair[0], by) > sort_key(doc, pair[1], by))?[1];
+
+            let name = unsorted.name(doc);
+            let child = match by {
+                SortKey::Attribute(attr) => match unsorted.attribute(doc, attr) {
+                    Some(value) => format!("{} {}=\"{}\"", name, attr, value.escape_debug()),
+                    None => name.to_string(),
+                },
+                SortKey::ChildText { names, .. } => {
+                    let texts = names
+                        .iter()
+                        .map(|n| child_text(doc, unsorted, n).unwrap_or_default())
+                        .collect::<Vec<_>>();
+                    format!(
+                        "{} {}=\"{}\"",
+                        name,
+                        names.join("/"),
+                        texts.join("/").escape_debug()
+                    )
+                }
+                SortKey::TagName => name.to_string(),
+            };
+
+            Some(UnsortedChild {
+                path: element_path(doc, parent),
+                child,
+                location: locator.element(unsorted),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(xml: &str, parents: &str, by: &str) -> (String, Vec<String>) {
+        let mut doc = Document::from_str(xml).unwrap();
+        let skipped = sort_children(&mut doc, &parents.parse().unwrap(), &by.parse().unwrap());
+        (doc.to_string(), skipped)
+    }
+
+    #[test]
+    fn comments_move_with_the_following_element() {
+        assert_eq!(
+            sorted(
+                "<r><!-- about c --><c/><!-- about a --><!-- more --><a/><b/><!-- trailing --></r>",
+                "r",
+                "name"
+            ),
+            (
+                "<r><!-- about a --><!-- more --><a/><b/><!-- about c --><c/><!-- trailing --></r>"
+                    .to_string(),
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn mixed_content_is_skipped_and_reported() {
+        let xml = "<r><p><b/>text<a/></p><q><b/><a/></q></r>";
+        assert_eq!(
+            sorted(xml, "p, q", "name"),
+            (
+                "<r><p><b/>text<a/></p><q><a/><b/></q></r>".to_string(),
+                vec!["p".to_string()]
+            )
+        );
+        let cdata = "<r><![CDATA[x]]><b/><a/></r>";
+        assert_eq!(sorted(cdata, "r", "name").1, ["r"]);
+    }
+
+    #[test]
+    fn child_text_keys_are_compared_in_turn() {
+        let xml = "<deps>\
+            <dep><g>b</g><a>1</a></dep>\
+            <dep><a>2</a></dep>\
+            <dep><g>a</g><a>2</a></dep>\
+            <dep><g>a</g><a>1</a></dep>\
+            </deps>";
+        assert_eq!(
+            sorted(xml, "deps", "key=g/a").0,
+            "<deps>\
+            <dep><g>a</g><a>1</a></dep>\
+            <dep><g>a</g><a>2</a></dep>\
+            <dep><g>b</g><a>1</a></dep>\
+            <dep><a>2</a></dep>\
+            </deps>"
+        );
+    }
+
+    #[test]
+    fn icase_child_text_keys_ignore_case() {
+        let xml = "<r><e><k>b</k></e><e><k>B</k></e><e><k>a</k></e><e><k>C</k></e></r>";
+        // Code point order puts upper case first; equal keys keep their order.
+        assert_eq!(
+            sorted(xml, "r", "key=k").0,
+            "<r><e><k>B</k></e><e><k>C</k></e><e><k>a</k></e><e><k>b</k></e></r>"
+        );
+        assert_eq!(
+            sorted(xml, "r", "key=k:icase").0,
+            "<r><e><k>a</k></e><e><k>b</k></e><e><k>B</k></e><e><k>C</k></e></r>"
+        );
+    }
+
+    #[test]
+    fn parses_sort_keys() {
+        assert_eq!(
+            "key=groupId/artifactId:icase".parse(),
+            Ok(SortKey::ChildText {
+                names: vec!["groupId".to_string(), "artifactId".to_string()],
+                is_case_insensitive: true,
+            })
+        );
+        assert_eq!("@id".parse(), Ok(SortKey::Attribute("id".to_string())));
+        assert!("key=a//b".parse::<SortKey>().is_err());
+        assert!("@".parse::<SortKey>().is_err());
+    }
+}
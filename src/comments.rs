@@ -0,0 +1,281 @@
+use xmlem::{Document, Element, Node};
+
+/// Re-wraps the lines of comments that would be longer than `max_line_length` once indented
+/// `indent` spaces per level, breaking them at spaces. With `width`, the limit is instead
+/// `width` columns after the comment's own indentation.
+///
+/// Only overlong lines are touched: a comment that fits is left exactly as written, and line
+/// breaks already in a comment are kept, so each line is wrapped on its own and lines are never
+/// joined. A first line that is a title such as `==== Settings ====` is kept whole. Comments
+/// next to text are skipped, since where they start depends on the text.
+pub fn wrap_comments(
+    doc: &mut Document,
+    indent: usize,
+    max_line_length: usize,
+    width: Option<usize>,
+) {
+    let root = doc.root();
+    let parents = std::iter::once(root)
+        .chain(root.walk(doc))
+        .collect::<Vec<Element>>();
+
+    for parent in parents {
+        let children = parent.child_nodes(doc).to_vec();
+        let is_mixed = children.iter().any(|node| match node {
+            Node::Text(text) => !text.as_str(doc).trim().is_empty(),
+            Node::CDataSection(_) | Node::ProcessingInstruction(_) => true,
+            _ => false,
+        });
+        if is_mixed {
+            continue;
+        }
+
+        let column = depth(doc, parent) * indent;
+        let limit = width.map_or(max_line_length, |width| column.saturating_add(width));
+        let wrapped = children
+            .iter()
+            .map(|node| match node {
+                Node::Comment(comment) => wrap(comment.as_str(doc), column, limit),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if wrapped.iter().all(Option::is_none) {
+            continue;
+        }
+
+        // xmlem can only append children, so the whole list is rebuilt in order.
+        for node in children.iter() {
+            parent.remove_child(doc, *node);
+        }
+        for (node, wrapped) in children.into_iter().zip(wrapped) {
+            match (node, wrapped) {
+                (_, Some(text)) => {
+                    parent.append_comment(doc, &text);
+                }
+                (Node::Comment(comment), None) => {
+                    let text = comment.as_str(doc).to_string();
+                    parent.append_comment(doc, &text);
+                }
+                (Node::Element(element), None) => parent.append_element(doc, element),
+                (Node::Text(text), None) => {
+                    let text = text.as_str(doc).to_string();
+                    parent.append_text(doc, &text);
+                }
+                (Node::CDataSection(_) | Node::ProcessingInstruction(_), None) => {
+                    unreachable!("mixed content is skipped")
+                }
+                (Node::DocumentType(_), None) => {}
+            }
+        }
+    }
+}
+
+/// The depth of `element`, counting the root element as 1.
+fn depth(doc: &Document, element: Element) -> usize {
+    std::iter::successors(Some(element), |element| element.parent(doc)).count()
+}
+
+/// Whether `line` is a title such as `==== Settings ====`, which is never broken up.
+fn is_title(line: &str) -> bool {
+    let line = line.trim();
+    let mut chars = line.chars();
+    match (chars.next(), chars.next(), chars.next_back()) {
+        (Some(c), Some(second), Some(last)) => "=*#~+".contains(c) && second == c && last == c,
+        _ => false,
+    }
+}
+
+/// The text of the comment `text`, starting at `column`, with each line that would end past
+/// `limit` broken at spaces onto lines of its own, or `None` if no line needs breaking.
+fn wrap(text: &str, column: usize, limit: usize) -> Option<String> {
+    let lines = text.split('\n').collect::<Vec<_>>();
+    let mut output = Vec::with_capacity(lines.len());
+    let mut is_changed = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let (line, cr) = match line.strip_suffix('\r') {
+            Some(line) => (line, "\r"),
+            None => (*line, ""),
+        };
+        let is_first = i == 0;
+        let is_last = i == lines.len() - 1;
+
+        // The first line follows `<!--` on the comment's own line; the others are written as
+        // they are, starting at the left margin.
+        let start = if is_first { column + "<!--".len() } else { 0 };
+        let end = if is_last { "-->".len() } else { 0 };
+        let length = start + line.chars().count() + end;
+        if length <= limit || (is_first && is_title(line)) {
+            output.push(format!("{}{}", line, cr));
+            continue;
+        }
+
+        let content = line.trim_start();
+        let lead = &line[..line.len() - content.len()];
+        let content = content.trim_end();
+        // Kept on the last line, where dropping a space before `-->` could make `--->`.
+        let trail = if is_last {
+            &line[lead.len() + content.len()..]
+        } else {
+            ""
+        };
+        let continuation = if is_first {
+            format!("{}{}", " ".repeat(start), lead)
+        } else {
+            lead.to_string()
+        };
+
+        let mut pieces = vec![];
+        let mut piece = lead.to_string();
+        let mut piece_length = start + lead.chars().count();
+        let mut has_word = false;
+        for word in content.split(' ').filter(|word| !word.is_empty()) {
+            let word_length = word.chars().count();
+            if has_word && piece_length + 1 + word_length > limit {
+                pieces.push(std::mem::replace(&mut piece, continuation.clone()));
+                piece_length = continuation.chars().count();
+                has_word = false;
+            }
+            if has_word {
+                piece.push(' ');
+                piece_length += 1;
+            }
+            piece.push_str(word);
+            piece_length += word_length;
+            has_word = true;
+        }
+        // The last piece of the last line also has to leave room for `-->`.
+        if is_last && piece_length + trail.len() + end > limit {
+            let words_start = if pieces.is_empty() {
+                lead.len()
+            } else {
+                continuation.len()
+            };
+            if let Some(space) = piece[words_start..].rfind(' ') {
+                let word = piece.split_off(words_start + space);
+                let next = format!("{}{}", continuation, &word[1..]);
+                pieces.push(std::mem::replace(&mut piece, next));
+            }
+        }
+        piece.push_str(trail);
+        pieces.push(piece);
+
+        is_changed |= pieces.len() > 1;
+        output.extend(pieces.into_iter().map(|piece| format!("{}{}", piece, cr)));
+    }
+
+    if !is_changed {
+        return None;
+    }
+    let wrapped = output.join("\n");
+    // Breaking a line only ever replaces spaces with a line break and indentation, but make
+    // sure nothing came out that would end the comment early.
+    if wrapped.contains("--") && !text.contains("--") {
+        return None;
+    }
+    Some(wrapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use xmlem::display::Config;
+
+    use super::*;
+
+    #[test]
+    fn leaves_comments_that_fit() {
+        assert_eq!(wrap(" short ", 0, 20), None);
+        assert_eq!(wrap(" one\n two ", 0, 10), None);
+    }
+
+    #[test]
+    fn breaks_overlong_lines_at_spaces() {
+        assert_eq!(
+            wrap(" one two three four ", 0, 16).as_deref(),
+            Some(" one two\n     three\n     four ")
+        );
+    }
+
+    #[test]
+    fn keeps_a_title_line() {
+        assert_eq!(
+            wrap(" ==== a long title line ====\n one two three ", 0, 16).as_deref(),
+            Some(" ==== a long title line ====\n one two\n three ")
+        );
+    }
+
+    #[test]
+    fn keeps_existing_line_breaks_and_crlf() {
+        assert_eq!(
+            wrap(" a b c d e f\r\n g ", 0, 10).as_deref(),
+            Some(" a b c\r\n     d e f\r\n g ")
+        );
+    }
+
+    #[test]
+    fn leaves_room_for_the_end_of_the_comment() {
+        let wrapped = wrap(" aaaa bbbb", 0, 15).unwrap();
+        assert_eq!(wrapped, " aaaa\n     bbbb");
+        assert!(wrapped.lines().all(|line| line.len() + 4 <= 15));
+    }
+
+    #[test]
+    fn never_writes_a_double_hyphen() {
+        // The space before `-->` is kept so the comment cannot end in `--->`.
+        assert_eq!(
+            wrap(" one two three - ", 0, 14).as_deref(),
+            Some(" one two\n     three\n     - ")
+        );
+    }
+
+    #[test]
+    fn keeps_greater_than_and_hyphens_in_words() {
+        assert_eq!(
+            wrap(" a->b x-y c>d e ", 0, 14).as_deref(),
+            Some(" a->b x-y\n     c>d e ")
+        );
+    }
+
+    #[test]
+    fn long_words_are_not_broken() {
+        assert_eq!(
+            wrap(" abcdefghijklmnop q ", 0, 10).as_deref(),
+            Some(" abcdefghijklmnop\n     q ")
+        );
+    }
+
+    #[test]
+    fn recognises_titles() {
+        assert!(is_title("==== Settings ===="));
+        assert!(is_title(" ## x ## "));
+        assert!(!is_title("= x ="));
+        assert!(!is_title("==== x"));
+    }
+
+    fn wrapped_document(source: &str, max_line_length: usize) -> String {
+        let mut doc = Document::from_str(source).unwrap();
+        wrap_comments(&mut doc, 2, max_line_length, None);
+        doc.to_string_pretty_with_config(&Config::default_pretty().max_line_length(max_line_length))
+    }
+
+    #[test]
+    fn wraps_comments_in_a_document_once() {
+        let source = "<r><a/><!-- one two three four five six --><b>t</b></r>";
+        let once = wrapped_document(source, 24);
+        assert_eq!(
+            once,
+            "<r>\n  <a/>\n  <!-- one two three\n       four five six -->\n  <b>\n    t\n  </b>\n</r>\n"
+        );
+        assert_eq!(wrapped_document(&once, 24), once);
+    }
+
+    #[test]
+    fn skips_comments_next_to_text() {
+        let source = "<r>text <!-- one two three four five six --></r>";
+        let mut doc = Document::from_str(source).unwrap();
+        wrap_comments(&mut doc, 2, 10, None);
+        assert_eq!(doc.to_string(), source);
+    }
+}
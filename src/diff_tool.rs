@@ -0,0 +1,129 @@
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    process::Command,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Distinguishes the temporary files of the documents of one run.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary file, removed when dropped, so it goes away however the tool ends.
+struct TempFile(PathBuf);
+
+impl TempFile {
+    fn create(id: usize, name: &str, contents: &str) -> io::Result<Self> {
+        let path = env::temp_dir().join(format!(
+            "xml-pretty-{}-{}.{}.xml",
+            std::process::id(),
+            id,
+            name
+        ));
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        let temp = TempFile(path);
+        file.write_all(contents.as_bytes())?;
+        Ok(temp)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// Splits `command` into the program and its arguments the way a POSIX shell would, with
+/// `'...'`, `"..."` and `\` quoting, but without expanding anything. The paths of the two
+/// files replace `{old}` and `{new}`, or are appended if the command has neither.
+pub fn arguments(
+    command: &str,
+    old_path: &str,
+    new_path: &str,
+) -> Result<Vec<String>, shell_words::ParseError> {
+    let mut words = shell_words::split(command)?
+        .into_iter()
+        .map(|word| word.replace("{old}", old_path).replace("{new}", new_path))
+        .collect::<Vec<_>>();
+    if !command.contains("{old}") && !command.contains("{new}") {
+        words.extend([old_path.to_string(), new_path.to_string()]);
+    }
+    Ok(words)
+}
+
+/// Shows how `formatted` differs from `original` with the user's `--diff-tool` command. The
+/// two are written to temporary files, which the command is given as split by [`arguments`].
+/// It is run directly, not through a shell. Returns a message to warn with if the tool could
+/// not be run or failed; a status of 1, which diff tools use to say the files differ, is not a
+/// failure.
+pub fn run(command: &str, original: &str, formatted: &str) -> Option<String> {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let files = TempFile::create(id, "old", original)
+        .and_then(|old| Ok((old, TempFile::create(id, "new", formatted)?)));
+    let (old, new) = match files {
+        Ok(files) => files,
+        Err(e) => return Some(format!("could not write files for the diff tool: {}", e)),
+    };
+    let old_path = old.0.to_string_lossy();
+    let new_path = new.0.to_string_lossy();
+
+    let words = match arguments(command, &old_path, &new_path) {
+        Ok(words) => words,
+        Err(e) => return Some(format!("could not run diff tool: {}", e)),
+    };
+    let (program, args) = words.split_first()?;
+
+    // Anything already printed has to come before what the tool prints.
+    let _ = io::stdout().flush();
+    match Command::new(program).args(args).status() {
+        Ok(status) if status.success() || status.code() == Some(1) => None,
+        Ok(status) => Some(format!("diff tool '{}' {}", program, status)),
+        Err(e) => Some(format!("could not run diff tool '{}': {}", program, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn substitutes_the_paths() {
+        assert_eq!(
+            arguments("difft --color=always {old} {new}", "/t/a", "/t/b").unwrap(),
+            ["difft", "--color=always", "/t/a", "/t/b"]
+        );
+        assert_eq!(
+            arguments("diff -u --label={old}", "/t/a", "/t/b").unwrap(),
+            ["diff", "-u", "--label=/t/a"]
+        );
+        assert_eq!(
+            arguments("diff -u", "/t/a", "/t/b").unwrap(),
+            ["diff", "-u", "/t/a", "/t/b"]
+        );
+    }
+
+    #[test]
+    fn splits_like_a_shell() {
+        assert_eq!(
+            arguments(
+                r#"sh -c 'diff "$0" "$1" | less' {old} "{new}" a\ b"#,
+                "/t/a b",
+                "/t/c"
+            )
+            .unwrap(),
+            [
+                "sh",
+                "-c",
+                r#"diff "$0" "$1" | less"#,
+                "/t/a b",
+                "/t/c",
+                "a b"
+            ]
+        );
+        assert!(arguments("diff 'unterminated", "/t/a", "/t/b").is_err());
+    }
+}
@@ -0,0 +1,79 @@
+use std::fmt::Write;
+
+use xmlem::{Document, Element, Node};
+
+/// Prints the parsed form of `doc` as an indented list of nodes, one per line, so it can be
+/// compared with the formatted output to tell a parser problem from a serializer one. Text is
+/// quoted with spaces shown as `·`, tabs as `→` and line breaks as `↵`.
+///
+/// xmlem does not expose comments and processing instructions outside the root element, so
+/// only the declaration, the doctype and the root element's tree are shown.
+pub fn dump(doc: &Document) -> String {
+    let mut output = String::new();
+
+    if let Some(decl) = doc.declaration() {
+        output.push_str("Declaration");
+        for (name, value) in [
+            ("version", &decl.version),
+            ("encoding", &decl.encoding),
+            ("standalone", &decl.standalone),
+        ] {
+            if let Some(value) = value {
+                let _ = write!(output, " {}=\"{}\"", name, visible(value));
+            }
+        }
+        output.push('\n');
+    }
+
+    if let Some(doctype) = doc.doctype() {
+        let _ = writeln!(output, "DocumentType \"{}\"", visible(doctype));
+    }
+
+    dump_element(&mut output, doc, doc.root(), 0);
+    output
+}
+
+fn dump_element(output: &mut String, doc: &Document, element: Element, depth: usize) {
+    let _ = write!(
+        output,
+        "{}Element <{}>",
+        "  ".repeat(depth),
+        element.qname(doc).prefixed_name()
+    );
+    for (name, value) in element.attributes(doc) {
+        let _ = write!(output, " {}=\"{}\"", name.prefixed_name(), visible(value));
+    }
+    output.push('\n');
+
+    let indent = "  ".repeat(depth + 1);
+    for node in element.child_nodes(doc) {
+        let (kind, text) = match node {
+            Node::Element(child) => {
+                dump_element(output, doc, *child, depth + 1);
+                continue;
+            }
+            Node::Text(text) => ("Text", text.as_str(doc)),
+            Node::CDataSection(cdata) => ("CDataSection", cdata.as_str(doc)),
+            Node::Comment(comment) => ("Comment", comment.as_str(doc)),
+            Node::ProcessingInstruction(pi) => ("ProcessingInstruction", pi.as_str(doc)),
+            Node::DocumentType(_) => ("DocumentType", ""),
+        };
+        let _ = writeln!(output, "{}{} \"{}\"", indent, kind, visible(text));
+    }
+}
+
+/// `text` with its whitespace made visible, and quotes and other control characters escaped.
+fn visible(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            ' ' => output.push('·'),
+            '\t' => output.push('→'),
+            '\n' => output.push('↵'),
+            '"' => output.push_str("\\\""),
+            c if c.is_control() => output.extend(c.escape_default()),
+            c => output.push(c),
+        }
+    }
+    output
+}
@@ -0,0 +1,255 @@
+//! Detection of documents whose declared encoding disagrees with their actual bytes.
+
+use std::fmt;
+
+use xmlem::Document;
+
+/// The encoding the bytes of a document are actually in, as far as can be told cheaply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detected {
+    Utf8,
+    Utf16,
+    /// Not valid UTF-8, so most likely a single-byte encoding such as ISO-8859-1.
+    NotUtf8,
+}
+
+impl fmt::Display for Detected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Detected::Utf8 => "UTF-8",
+            Detected::Utf16 => "UTF-16",
+            Detected::NotUtf8 => "a non-UTF-8 encoding",
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+    /// The declared encoding, or `None` if the declaration leaves it to default to UTF-8.
+    pub declared: Option<String>,
+    pub detected: Detected,
+    /// Byte offset of the first byte that contradicts the declaration.
+    pub offset: usize,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.declared.as_deref() {
+            Some(declared) => write!(f, "declared encoding '{}'", declared)?,
+            None => write!(f, "implied encoding UTF-8")?,
+        }
+        write!(
+            f,
+            " does not match the content, which is {} (first offending byte at offset {})",
+            self.detected, self.offset
+        )
+    }
+}
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+fn is_utf8_name(name: &str) -> bool {
+    name.eq_ignore_ascii_case("utf-8") || name.eq_ignore_ascii_case("utf8")
+}
+
+fn is_utf16_name(name: &str) -> bool {
+    name.get(..6)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("utf-16"))
+}
+
+/// Compares the encoding named in the XML declaration of `bytes` (taking its absence to mean
+/// UTF-8) with the byte order mark and the validity of the bytes as UTF-8.
+pub fn check(bytes: &[u8]) -> Option<Mismatch> {
+    let detected_bom_or_nul = if bytes.starts_with(UTF8_BOM) {
+        Some(Detected::Utf8)
+    } else if bytes.starts_with(b"\xFE\xFF")
+        || bytes.starts_with(b"\xFF\xFE")
+        || bytes.starts_with(b"\0<")
+        || bytes.starts_with(b"<\0")
+    {
+        Some(Detected::Utf16)
+    } else {
+        None
+    };
+
+    let declared = declared_encoding(bytes);
+    let mismatch = |detected, offset| Mismatch {
+        declared: declared.clone(),
+        detected,
+        offset,
+    };
+
+    match (detected_bom_or_nul, declared.as_deref()) {
+        (Some(Detected::Utf16), Some(name)) if is_utf16_name(name) => None,
+        (Some(Detected::Utf16), _) => Some(mismatch(Detected::Utf16, 0)),
+        (_, Some(name)) if is_utf16_name(name) => Some(mismatch(Detected::Utf8, 0)),
+        (Some(Detected::Utf8), Some(name)) if !is_utf8_name(name) => {
+            Some(mismatch(Detected::Utf8, 0))
+        }
+        (_, None) => match std::str::from_utf8(bytes) {
+            Ok(_) => None,
+            Err(e) => Some(mismatch(Detected::NotUtf8, e.valid_up_to())),
+        },
+        (_, Some(name)) if is_utf8_name(name) => match std::str::from_utf8(bytes) {
+            Ok(_) => None,
+            Err(e) => Some(mismatch(Detected::NotUtf8, e.valid_up_to())),
+        },
+        // Some other, ASCII-compatible encoding is declared. Pure ASCII is valid in all of
+        // them, but non-ASCII bytes that happen to form valid UTF-8 almost certainly are.
+        (_, Some(_)) => match std::str::from_utf8(bytes) {
+            Ok(_) => bytes
+                .iter()
+                .position(|b| !b.is_ascii())
+                .map(|offset| mismatch(Detected::Utf8, offset)),
+            Err(_) => None,
+        },
+    }
+}
+
+/// Extracts the `encoding` pseudo-attribute from the XML declaration, if any. UTF-16 input
+/// is handled by ignoring its NUL bytes, which is enough for the ASCII-only declaration.
+fn declared_encoding(bytes: &[u8]) -> Option<String> {
+    let prefix = bytes
+        .iter()
+        .take(1024)
+        .copied()
+        .filter(|b| *b != 0)
+        .collect::<Vec<_>>();
+    let prefix = String::from_utf8_lossy(&prefix);
+    let prefix = prefix.trim_start_matches(['\u{FEFF}', '\u{FFFD}']);
+
+    let decl = prefix.strip_prefix("<?xml")?;
+    let decl = &decl[..decl.find("?>")?];
+    let rest = &decl[decl.find("encoding")? + "encoding".len()..];
+    let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+    let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let rest = &rest[1..];
+    Some(rest[..rest.find(quote)?].to_string())
+}
+
+/// Makes the declaration name the encoding xml-pretty actually writes, which is always UTF-8.
+pub fn fix_declaration(doc: &mut Document) {
+    if let Some(decl) = doc.declaration() {
+        if decl
+            .encoding
+            .as_deref()
+            .is_some_and(|encoding| !encoding.eq_ignore_ascii_case("utf-8"))
+        {
+            let mut decl = decl.clone();
+            decl.encoding = Some("UTF-8".to_string());
+            doc.set_declaration(Some(decl));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn mismatch(bytes: &[u8]) -> Option<String> {
+        check(bytes).map(|mismatch| mismatch.to_string())
+    }
+
+    /// `s` in UTF-16, with a byte order mark.
+    fn utf16(s: &str, is_big_endian: bool) -> Vec<u8> {
+        "\u{feff}"
+            .encode_utf16()
+            .chain(s.encode_utf16())
+            .flat_map(|unit| {
+                if is_big_endian {
+                    unit.to_be_bytes()
+                } else {
+                    unit.to_le_bytes()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_declaration_contradicted_by_the_byte_order_mark_is_a_mismatch() {
+        assert_eq!(
+            mismatch(b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><r/>"),
+            Some("declared encoding 'ISO-8859-1' does not match the content, which is UTF-8 (first offending byte at offset 0)".to_string())
+        );
+        assert_eq!(
+            mismatch(&utf16("<?xml version=\"1.0\"?><r/>", false)),
+            Some("implied encoding UTF-8 does not match the content, which is UTF-16 (first offending byte at offset 0)".to_string())
+        );
+        assert_eq!(
+            mismatch(b"<?xml version=\"1.0\" encoding=\"UTF-16\"?><r/>"),
+            Some("declared encoding 'UTF-16' does not match the content, which is UTF-8 (first offending byte at offset 0)".to_string())
+        );
+    }
+
+    #[test]
+    fn invalid_utf8_under_a_utf8_declaration_is_a_mismatch() {
+        for bytes in [
+            &b"<?xml version=\"1.0\" encoding=\"UTF-8\"?><r>caf\xE9</r>"[..],
+            b"<?xml version='1.0' encoding='utf8'?><r>caf\xE9</r>",
+        ] {
+            let found = check(bytes).unwrap();
+            assert_eq!(found.detected, Detected::NotUtf8);
+            assert_eq!(found.offset, bytes.iter().position(|b| *b == 0xE9).unwrap());
+        }
+        assert_eq!(
+            mismatch(b"<r>caf\xE9</r>"),
+            Some("implied encoding UTF-8 does not match the content, which is a non-UTF-8 encoding (first offending byte at offset 6)".to_string())
+        );
+    }
+
+    #[test]
+    fn utf8_under_a_single_byte_declaration_is_a_mismatch() {
+        assert_eq!(
+            mismatch("<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><r>café</r>".as_bytes()),
+            Some("declared encoding 'ISO-8859-1' does not match the content, which is UTF-8 (first offending byte at offset 49)".to_string())
+        );
+        // ASCII, or bytes that are not UTF-8, are what the declaration says they could be.
+        assert_eq!(
+            mismatch(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><r>cafe</r>"),
+            None
+        );
+        assert_eq!(
+            mismatch(b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><r>caf\xE9</r>"),
+            None
+        );
+    }
+
+    #[test]
+    fn matching_encodings_are_not_mismatches() {
+        assert_eq!(mismatch("<r>café</r>".as_bytes()), None);
+        assert_eq!(
+            mismatch("\u{feff}<?xml version=\"1.0\" encoding=\"UTF-8\"?><r>café</r>".as_bytes()),
+            None
+        );
+        assert_eq!(
+            mismatch(&utf16(
+                "<?xml version=\"1.0\" encoding=\"UTF-16\"?><r/>",
+                true
+            )),
+            None
+        );
+    }
+
+    #[test]
+    fn the_fixed_declaration_names_utf8() {
+        let fixed = |xml: &str| {
+            let mut doc = Document::from_str(xml).unwrap();
+            fix_declaration(&mut doc);
+            doc.to_string()
+        };
+        assert_eq!(
+            fixed("<?xml version=\"1.0\" encoding=\"ISO-8859-1\" standalone=\"yes\"?><r/>"),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?><r/>"
+        );
+        // Declarations that already say UTF-8, or say nothing, are left alone.
+        for xml in [
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><r/>",
+            "<?xml version=\"1.0\"?><r/>",
+            "<r/>",
+        ] {
+            assert_eq!(fixed(xml), xml);
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Structural comparison of two parsed documents, ignoring differences that do not change
+//! their meaning: insignificant whitespace, attribute order and quote style.
+
+use std::{collections::BTreeSet, fmt};
+
+use xmlem::{Document, Element, Node};
+
+/// The first place where two documents differ.
+#[derive(Debug)]
+pub struct Difference {
+    pub path: String,
+    pub left: String,
+    pub right: String,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "documents differ at {}", self.path)?;
+        writeln!(f, "  left:  {}", self.left)?;
+        write!(f, "  right: {}", self.right)
+    }
+}
+
+const MISSING: &str = "(missing)";
+
+/// Returns the first difference between `left` and `right`, or `None` if the documents are
+/// equivalent.
+pub fn compare(left: &Document, right: &Document) -> Option<Difference> {
+    if left.doctype() != right.doctype() {
+        return Some(Difference {
+            path: "<!DOCTYPE>".to_string(),
+            left: left.doctype().unwrap_or(MISSING).to_string(),
+            right: right.doctype().unwrap_or(MISSING).to_string(),
+        });
+    }
+
+    let path = format!("/{}", left.root().name(left));
+    compare_elements(left, left.root(), right, right.root(), &path)
+}
+
+fn compare_elements(
+    left_doc: &Document,
+    left: Element,
+    right_doc: &Document,
+    right: Element,
+    path: &str,
+) -> Option<Difference> {
+    if left.name(left_doc) != right.name(right_doc) {
+        return Some(Difference {
+            path: path.to_string(),
+            left: format!("<{}>", left.name(left_doc)),
+            right: format!("<{}>", right.name(right_doc)),
+        });
+    }
+
+    let left_attrs = left.attributes(left_doc);
+    let right_attrs = right.attributes(right_doc);
+    let names = left_attrs
+        .keys()
+        .chain(right_attrs.keys())
+        .map(|name| name.prefixed_name())
+        .collect::<BTreeSet<_>>();
+
+    for name in names {
+        let left_value = left.attribute(left_doc, name);
+        let right_value = right.attribute(right_doc, name);
+        if left_value != right_value {
+            return Some(Difference {
+                path: format!("{}/@{}", path, name),
+                left: left_value.map_or(MISSING.to_string(), |v| format!("{:?}", v)),
+                right: right_value.map_or(MISSING.to_string(), |v| format!("{:?}", v)),
+            });
+        }
+    }
+
+    let left_children = significant_children(left_doc, left);
+    let right_children = significant_children(right_doc, right);
+
+    for i in 0..left_children.len().max(right_children.len()) {
+        let (left_child, right_child) = match (left_children.get(i), right_children.get(i)) {
+            (Some(l), Some(r)) => (l, r),
+            (l, r) => {
+                let path = match (l, r) {
+                    (Some(l), _) => child_path(path, left_doc, l),
+                    (_, Some(r)) => child_path(path, right_doc, r),
+                    (None, None) => unreachable!(),
+                };
+                return Some(Difference {
+                    path,
+                    left: l.map_or(MISSING.to_string(), |c| c.describe(left_doc)),
+                    right: r.map_or(MISSING.to_string(), |c| c.describe(right_doc)),
+                });
+            }
+        };
+
+        match (left_child, right_child) {
+            (Child::Element(l), Child::Element(r)) => {
+                let child_path = child_path(path, left_doc, left_child);
+                if let Some(difference) = compare_elements(left_doc, *l, right_doc, *r, &child_path)
+                {
+                    return Some(difference);
+                }
+            }
+            (l, r) if l.kind() == r.kind() && l.content() == r.content() => {}
+            (l, r) => {
+                return Some(Difference {
+                    path: child_path(path, left_doc, left_child),
+                    left: l.describe(left_doc),
+                    right: r.describe(right_doc),
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// XPath-like path of `child` within `doc`, with a position when it has siblings of the
+/// same name.
+fn child_path(path: &str, doc: &Document, child: &Child) -> String {
+    match child {
+        Child::Element(element) => {
+            let name = element.name(doc);
+            let siblings = element
+                .parent(doc)
+                .map(|parent| parent.children(doc))
+                .unwrap_or_default();
+            let same_name = siblings
+                .iter()
+                .filter(|sibling| sibling.name(doc) == name)
+                .collect::<Vec<_>>();
+            if same_name.len() > 1 {
+                let position = same_name.iter().position(|s| *s == element).unwrap() + 1;
+                format!("{}/{}[{}]", path, name, position)
+            } else {
+                format!("{}/{}", path, name)
+            }
+        }
+        other => format!("{}/{}", path, other.kind()),
+    }
+}
+
+enum Child {
+    Element(Element),
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction(String),
+}
+
+impl Child {
+    fn kind(&self) -> &'static str {
+        match self {
+            Child::Element(_) => "element()",
+            Child::Text(_) => "text()",
+            Child::CData(_) => "cdata()",
+            Child::Comment(_) => "comment()",
+            Child::ProcessingInstruction(_) => "processing-instruction()",
+        }
+    }
+
+    fn content(&self) -> Option<&str> {
+        match self {
+            Child::Element(_) => None,
+            Child::Text(s)
+            | Child::CData(s)
+            | Child::Comment(s)
+            | Child::ProcessingInstruction(s) => Some(s),
+        }
+    }
+
+    fn describe(&self, doc: &Document) -> String {
+        match self {
+            Child::Element(element) => format!("<{}>", element.name(doc)),
+            other => format!("{} {:?}", other.kind(), other.content().unwrap_or_default()),
+        }
+    }
+}
+
+/// Children that carry meaning: whitespace-only text is dropped and the remaining text is
+/// trimmed, as the pretty printer is free to change both.
+fn significant_children(doc: &Document, element: Element) -> Vec<Child> {
+    element
+        .child_nodes(doc)
+        .iter()
+        .filter_map(|node| match node {
+            Node::Element(e) => Some(Child::Element(*e)),
+            Node::Text(t) => {
+                let text = t.as_str(doc).trim();
+                (!text.is_empty()).then(|| Child::Text(text.to_string()))
+            }
+            Node::CDataSection(c) => Some(Child::CData(c.as_str(doc).to_string())),
+            Node::Comment(c) => Some(Child::Comment(c.as_str(doc).to_string())),
+            Node::ProcessingInstruction(p) => {
+                Some(Child::ProcessingInstruction(p.as_str(doc).to_string()))
+            }
+            Node::DocumentType(_) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,437 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    env,
+    fs::{self, File},
+    io::Read,
+    path::{self, Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Context;
+
+/// A document to process, found either on the command line or by recursing into a directory.
+#[derive(Debug)]
+pub struct Input {
+    pub path: PathBuf,
+    /// Found in a directory by the `--detect-xml` content sniffing rather than by extension.
+    pub is_sniffed: bool,
+}
+
+/// How input paths are shown in messages and output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathStyle {
+    /// Relative to the current directory when the file is inside it, absolute otherwise.
+    #[default]
+    Relative,
+    Absolute,
+    /// Exactly as given on the command line or found while walking a directory.
+    AsGiven,
+}
+
+impl FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "relative" => Ok(PathStyle::Relative),
+            "absolute" => Ok(PathStyle::Absolute),
+            "as-given" => Ok(PathStyle::AsGiven),
+            other => Err(format!(
+                "expected 'relative', 'absolute' or 'as-given', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// Rewrites `path` for display in `style`, so the same file is always shown the same way
+/// however it was spelled. Symlinks are not resolved.
+pub fn display_path(path: &Path, style: PathStyle) -> PathBuf {
+    if style == PathStyle::AsGiven {
+        return path.to_path_buf();
+    }
+
+    let absolute = match path::absolute(path) {
+        Ok(absolute) => absolute
+            .components()
+            .filter(|component| *component != Component::CurDir)
+            .collect::<PathBuf>(),
+        Err(_) => return path.to_path_buf(),
+    };
+
+    if style == PathStyle::Relative {
+        if let Some(relative) = env::current_dir()
+            .ok()
+            .and_then(|cwd| absolute.strip_prefix(cwd).ok().map(Path::to_path_buf))
+        {
+            return relative;
+        }
+    }
+
+    absolute
+}
+
+/// `path` with `/` as the separator on every platform, for output other tools match against.
+pub fn forward_slashes(path: &Path) -> String {
+    let path = path.to_string_lossy();
+    if path::MAIN_SEPARATOR == '/' {
+        path.into_owned()
+    } else {
+        path.replace(path::MAIN_SEPARATOR, "/")
+    }
+}
+
+/// Expands the paths given on the command line into the documents to process. Files are
+/// taken as they are; directories are searched recursively, in name order, for files with
+/// one of `extensions` (an allow-list) or, with `detect_xml`, files whose content looks
+/// like XML.
+///
+/// Problems found along the way, such as an unreadable directory or a named pipe with a
+/// matching name, are returned in place of the documents they affect, so one bad entry
+/// doesn't stop the rest from being processed.
+///
+/// Symlinked directories are followed, but each directory is only searched once, so a link
+/// back to a parent doesn't make the search go round in circles. Likewise a file reached by
+/// more than one path, e.g. given both directly and through a symlink, is only taken once, by
+/// the first of them. A file named on the command line that is left out this way is listed in
+/// [`Expanded::duplicates`], so it can be warned about.
+pub fn expand(paths: &[PathBuf], extensions: &[String], detect_xml: bool) -> Expanded {
+    let mut expanded = Expanded {
+        inputs: vec![],
+        duplicates: vec![],
+    };
+    let mut visited = HashMap::new();
+
+    for path in paths {
+        if path.is_dir() {
+            walk(
+                path,
+                extensions,
+                detect_xml,
+                &mut visited,
+                &mut expanded.inputs,
+            );
+        } else {
+            match earlier_visit(path, &mut visited) {
+                None => expanded.inputs.push(Ok(Input {
+                    path: path.clone(),
+                    is_sniffed: false,
+                })),
+                Some(first) => expanded.duplicates.push(Duplicate {
+                    path: path.clone(),
+                    first,
+                }),
+            }
+        }
+    }
+
+    expanded
+}
+
+/// The documents found by [`expand`].
+pub struct Expanded {
+    pub inputs: Vec<anyhow::Result<Input>>,
+    /// Files given on the command line that were already taken by another path.
+    pub duplicates: Vec<Duplicate>,
+}
+
+/// A file given on the command line as `path` that was already taken as `first`.
+pub struct Duplicate {
+    pub path: PathBuf,
+    pub first: PathBuf,
+}
+
+/// The path the file at `path` was first taken by, if it has been seen before. One that can't
+/// be resolved, e.g. because it doesn't exist, counts as new, leaving the error to whatever
+/// opens it.
+fn earlier_visit(path: &Path, visited: &mut HashMap<PathBuf, PathBuf>) -> Option<PathBuf> {
+    match visited.entry(fs::canonicalize(path).ok()?) {
+        Entry::Occupied(entry) => Some(entry.get().clone()),
+        Entry::Vacant(entry) => {
+            entry.insert(path.to_path_buf());
+            None
+        }
+    }
+}
+
+fn walk(
+    dir: &Path,
+    extensions: &[String],
+    detect_xml: bool,
+    visited: &mut HashMap<PathBuf, PathBuf>,
+    inputs: &mut Vec<anyhow::Result<Input>>,
+) {
+    match fs::canonicalize(dir) {
+        Ok(canonical) => {
+            if visited.insert(canonical, dir.to_path_buf()).is_some() {
+                return;
+            }
+        }
+        Err(e) => {
+            inputs.push(
+                Err(e).with_context(|| format!("Failed to read directory '{}'", dir.display())),
+            );
+            return;
+        }
+    }
+
+    let paths = fs::read_dir(dir).and_then(|entries| {
+        entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    let mut paths = match paths {
+        Ok(paths) => paths,
+        Err(e) => {
+            inputs.push(
+                Err(e).with_context(|| format!("Failed to read directory '{}'", dir.display())),
+            );
+            return;
+        }
+    };
+    paths.sort();
+
+    for path in paths {
+        // Entries can disappear or change between listing the directory and looking at them.
+        let file_type = match fs::metadata(&path) {
+            Ok(metadata) => metadata.file_type(),
+            Err(e) => {
+                inputs.push(Err(e).with_context(|| format!("Failed to read '{}'", path.display())));
+                continue;
+            }
+        };
+
+        if file_type.is_dir() {
+            walk(&path, extensions, detect_xml, visited, inputs);
+        } else if !file_type.is_file() {
+            // Opening a named pipe or a device could block forever or never end.
+            if has_extension(&path, extensions) {
+                inputs.push(Err(anyhow::anyhow!(
+                    "Skipped '{}': it is {}, not a regular file",
+                    path.display(),
+                    special_file_kind(file_type)
+                )));
+            }
+        } else {
+            let is_sniffed =
+                if has_extension(&path, extensions) {
+                    false
+                } else if detect_xml {
+                    match looks_like_xml(&path) {
+                        Ok(true) => true,
+                        Ok(false) => continue,
+                        Err(e) => {
+                            inputs.push(Err(e).with_context(|| {
+                                format!("Failed to read file '{}'", path.display())
+                            }));
+                            continue;
+                        }
+                    }
+                } else {
+                    continue;
+                };
+
+            if earlier_visit(&path, visited).is_none() {
+                inputs.push(Ok(Input { path, is_sniffed }));
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn special_file_kind(file_type: fs::FileType) -> &'static str {
+    use std::os::unix::fs::FileTypeExt;
+
+    if file_type.is_fifo() {
+        "a named pipe"
+    } else if file_type.is_socket() {
+        "a socket"
+    } else if file_type.is_block_device() || file_type.is_char_device() {
+        "a device"
+    } else {
+        "a special file"
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_file_type: fs::FileType) -> &'static str {
+    "a special file"
+}
+
+/// Matches the extension case-insensitively, looking through a trailing `.gz`.
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    let path = match path.extension() {
+        Some(ext) if ext.eq_ignore_ascii_case("gz") => Path::new(path.file_stem().unwrap()),
+        _ => path,
+    };
+
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+}
+
+/// Sniffs the start of a file: it looks like XML if its first non-whitespace bytes (after
+/// an optional UTF-8 byte order mark) are a declaration, comment or doctype, or a `<`
+/// followed by a name character. Anything containing a NUL byte is taken to be binary.
+fn looks_like_xml(path: &Path) -> std::io::Result<bool> {
+    let mut head = Vec::with_capacity(512);
+    File::open(path)?.take(512).read_to_end(&mut head)?;
+
+    if head.contains(&0) {
+        return Ok(false);
+    }
+
+    let head = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&head);
+    let start = head.iter().position(|b| !b.is_ascii_whitespace());
+    let head = match start {
+        Some(start) => &head[start..],
+        None => return Ok(false),
+    };
+
+    Ok(match head {
+        [b'<', b'?' | b'!', ..] => true,
+        [b'<', b, ..] => b.is_ascii_alphabetic() || *b == b'_' || *b == b':' || !b.is_ascii(),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of its own under the system temporary directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path =
+                env::temp_dir().join(format!("xml-pretty-files-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn found(inputs: &[anyhow::Result<Input>]) -> Vec<PathBuf> {
+        inputs
+            .iter()
+            .map(|input| input.as_ref().unwrap().path.clone())
+            .collect()
+    }
+
+    #[test]
+    fn walks_directories_in_name_order() {
+        let dir = TempDir::new("order");
+        fs::create_dir(dir.0.join("b")).unwrap();
+        fs::write(dir.0.join("b/x.xml"), "<x/>").unwrap();
+        fs::write(dir.0.join("a.xml"), "<a/>").unwrap();
+        fs::write(dir.0.join("c.txt"), "<c/>").unwrap();
+
+        let inputs = expand(std::slice::from_ref(&dir.0), &["xml".to_string()], false).inputs;
+        assert_eq!(found(&inputs), [dir.0.join("a.xml"), dir.0.join("b/x.xml")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loop_is_walked_once() {
+        let dir = TempDir::new("loop");
+        fs::create_dir(dir.0.join("d")).unwrap();
+        fs::write(dir.0.join("d/x.xml"), "<x/>").unwrap();
+        std::os::unix::fs::symlink("..", dir.0.join("d/up")).unwrap();
+
+        let inputs = expand(std::slice::from_ref(&dir.0), &["xml".to_string()], false).inputs;
+        assert_eq!(found(&inputs), [dir.0.join("d/x.xml")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn named_pipe_is_an_error_in_place_of_its_document() {
+        let dir = TempDir::new("fifo");
+        fs::write(dir.0.join("a.xml"), "<a/>").unwrap();
+        let fifo = dir.0.join("b.xml");
+        let status = std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let inputs = expand(std::slice::from_ref(&dir.0), &["xml".to_string()], false).inputs;
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs[0].as_ref().unwrap().path, dir.0.join("a.xml"));
+        let error = inputs[1].as_ref().unwrap_err().to_string();
+        assert!(error.contains("it is a named pipe"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_directory_elsewhere_is_followed() {
+        let dir = TempDir::new("linked");
+        fs::create_dir_all(dir.0.join("tree")).unwrap();
+        fs::create_dir_all(dir.0.join("other")).unwrap();
+        fs::write(dir.0.join("other/y.xml"), "<y/>").unwrap();
+        std::os::unix::fs::symlink("../other", dir.0.join("tree/link")).unwrap();
+
+        let inputs = expand(&[dir.0.join("tree")], &["xml".to_string()], false).inputs;
+        assert_eq!(found(&inputs), [dir.0.join("tree/link/y.xml")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn file_and_symlink_to_it_are_taken_once() {
+        let dir = TempDir::new("same-file");
+        let file = dir.0.join("a.xml");
+        fs::write(&file, "<a/>").unwrap();
+        std::os::unix::fs::symlink("a.xml", dir.0.join("b.xml")).unwrap();
+        fs::write(dir.0.join("notes.txt"), "<notes/>").unwrap();
+
+        let extensions = ["xml".to_string()];
+        let expanded = expand(&[file.clone(), dir.0.join("b.xml")], &extensions, false);
+        assert_eq!(found(&expanded.inputs), std::slice::from_ref(&file));
+        assert_eq!(expanded.duplicates.len(), 1);
+        assert_eq!(expanded.duplicates[0].path, dir.0.join("b.xml"));
+        assert_eq!(expanded.duplicates[0].first, file);
+        // Only files given directly are reported.
+        let expanded = expand(&[dir.0.join("b.xml"), dir.0.clone()], &extensions, false);
+        assert_eq!(found(&expanded.inputs), [dir.0.join("b.xml")]);
+        assert!(expanded.duplicates.is_empty());
+
+        // A file the search passes over is still taken when given directly.
+        let notes = dir.0.join("notes.txt");
+        let inputs = expand(&[dir.0.clone(), notes.clone()], &extensions, false).inputs;
+        assert_eq!(found(&inputs), [file, notes]);
+    }
+
+    #[test]
+    fn repeated_file_is_taken_once_and_reported() {
+        let dir = TempDir::new("repeated");
+        let file = dir.0.join("a.xml");
+        fs::write(&file, "<a/>").unwrap();
+
+        let expanded = expand(&[file.clone(), file.clone()], &["xml".to_string()], false);
+        assert_eq!(found(&expanded.inputs), std::slice::from_ref(&file));
+        assert_eq!(expanded.duplicates.len(), 1);
+        assert_eq!(
+            (&expanded.duplicates[0].path, &expanded.duplicates[0].first),
+            (&file, &file)
+        );
+    }
+
+    #[test]
+    fn missing_files_are_kept_for_the_error() {
+        let dir = TempDir::new("missing");
+        let missing = dir.0.join("missing.xml");
+        let inputs = expand(
+            &[missing.clone(), missing.clone()],
+            &["xml".to_string()],
+            false,
+        )
+        .inputs;
+        assert_eq!(found(&inputs), [missing.clone(), missing]);
+    }
+}
@@ -0,0 +1,64 @@
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Whether `path` names a gzip file by extension, e.g. `assets.xml.gz`.
+pub fn has_gz_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "gz")
+}
+
+pub fn is_gzip(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+pub fn decompress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    GzDecoder::new(bytes).read_to_end(&mut output)?;
+    Ok(output)
+}
+
+pub fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let document = "<r>\n  <a>é</a>\n</r>\n".repeat(100);
+        let compressed = compress(document.as_bytes()).unwrap();
+        assert!(is_gzip(&compressed));
+        assert!(compressed.len() < document.len());
+        assert_eq!(decompress(&compressed).unwrap(), document.as_bytes());
+    }
+
+    #[test]
+    fn detects_gzip_by_its_magic_bytes() {
+        assert!(is_gzip(&[0x1f, 0x8b, 0x08]));
+        assert!(!is_gzip(b"<r/>"));
+        assert!(!is_gzip(&[0x1f]));
+        assert!(!is_gzip(&[]));
+    }
+
+    #[test]
+    fn detects_gzip_by_extension() {
+        assert!(has_gz_extension(Path::new("assets.xml.gz")));
+        assert!(!has_gz_extension(Path::new("assets.xml")));
+        assert!(!has_gz_extension(Path::new("gz")));
+    }
+
+    #[test]
+    fn truncated_data_is_an_error() {
+        let compressed = compress(b"<r/>").unwrap();
+        assert!(decompress(&compressed[..compressed.len() / 2]).is_err());
+    }
+}
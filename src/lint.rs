@@ -0,0 +1,411 @@
+use std::borrow::Cow;
+
+/// How strictly `--lint` compares the formatted output against the original document.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Comparison {
+    /// Treat `\r\n`, `\r` and `\n` line endings as equal.
+    pub is_newline_insensitive: bool,
+    /// Ignore spaces and tabs at the end of each line.
+    pub is_trailing_space_insensitive: bool,
+}
+
+impl Comparison {
+    pub fn is_equal(&self, formatted: &str, original: &str) -> bool {
+        formatted == original || self.normalize(formatted) == self.normalize(original)
+    }
+
+    /// Applies the configured insensitivities to `input`, so that anything displayed from the
+    /// result agrees with the outcome of [`Comparison::is_equal`].
+    pub fn normalize<'a>(&self, input: &'a str) -> Cow<'a, str> {
+        let mut output = Cow::Borrowed(input);
+
+        if self.is_newline_insensitive && output.contains('\r') {
+            output = Cow::Owned(output.replace("\r\n", "\n").replace('\r', "\n"));
+        }
+
+        if self.is_trailing_space_insensitive {
+            let mut trimmed = String::with_capacity(output.len());
+            for (i, line) in output.split('\n').enumerate() {
+                if i > 0 {
+                    trimmed.push('\n');
+                }
+                match line.strip_suffix('\r') {
+                    Some(line) => {
+                        trimmed.push_str(line.trim_end_matches([' ', '\t']));
+                        trimmed.push('\r');
+                    }
+                    None => trimmed.push_str(line.trim_end_matches([' ', '\t'])),
+                }
+            }
+            if trimmed.len() != output.len() {
+                output = Cow::Owned(trimmed);
+            }
+        }
+
+        output
+    }
+}
+
+/// A document that `--lint` found not to be formatted.
+#[derive(Debug)]
+pub struct NotFormatted {
+    pub input_name: String,
+    /// What kinds of difference there are, as named by [`classify`].
+    pub categories: Vec<&'static str>,
+    /// The line and column of the first difference, both numbered from 1.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for NotFormatted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "xml-pretty --lint failed for document {} ({})",
+            self.input_name,
+            self.categories.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for NotFormatted {}
+
+/// Names the kinds of difference between `original` and its `formatted` output, so a lint
+/// failure can be triaged: whitespace-only categories are safe to fix automatically,
+/// `other-structural` needs a look. Each dimension is normalized away in turn, always in the
+/// same order, until the two agree.
+pub fn classify(formatted: &str, original: &str) -> Vec<&'static str> {
+    let mut categories = vec![];
+
+    let mut original = Cow::Borrowed(original);
+    let mut formatted = Cow::Borrowed(formatted);
+
+    // Output that keeps the source's byte order mark or line endings, as `--reindent-only`
+    // does, does not differ in them.
+    if let (Some(rest), false) = (
+        original.strip_prefix('\u{feff}'),
+        formatted.starts_with('\u{feff}'),
+    ) {
+        categories.push("bom");
+        original = Cow::Owned(rest.to_string());
+    }
+
+    let line_endings = Comparison {
+        is_newline_insensitive: true,
+        is_trailing_space_insensitive: false,
+    };
+    if original.contains('\r') && !formatted.contains('\r') {
+        categories.push("line-endings");
+        original = Cow::Owned(line_endings.normalize(&original).into_owned());
+        formatted = Cow::Owned(line_endings.normalize(&formatted).into_owned());
+    }
+
+    let trailing_space = Comparison {
+        is_newline_insensitive: false,
+        is_trailing_space_insensitive: true,
+    };
+    let has_trailing_space = original
+        .split('\n')
+        .any(|line| line.trim_end_matches('\r').ends_with([' ', '\t']));
+    if has_trailing_space {
+        categories.push("trailing-whitespace");
+        original = Cow::Owned(trailing_space.normalize(&original).into_owned());
+        formatted = Cow::Owned(trailing_space.normalize(&formatted).into_owned());
+    }
+
+    if original.len() - original.trim_end_matches('\n').len()
+        != formatted.len() - formatted.trim_end_matches('\n').len()
+    {
+        categories.push("final-newline");
+    }
+    let original = original.trim_end_matches('\n');
+    let formatted = formatted.trim_end_matches('\n');
+
+    if original == formatted {
+        return categories;
+    }
+
+    // Compared lazily, since a minified document can be a single line of many megabytes.
+    if unindented(original).eq(unindented(formatted)) {
+        categories.push("indentation");
+        return categories;
+    }
+
+    // Elements put on lines of their own, as when a minified document is formatted, only
+    // change whitespace too.
+    if between_tags_collapsed(original) == between_tags_collapsed(formatted) {
+        categories.push("indentation");
+        return categories;
+    }
+
+    if original.split_whitespace().eq(formatted.split_whitespace()) {
+        categories.push("attribute-wrapping");
+    } else {
+        categories.push("other-structural");
+    }
+
+    categories
+}
+
+/// `s` without the whitespace that follows a `>` or comes before a `<`.
+fn between_tags_collapsed(s: &str) -> String {
+    let mut collapsed = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find(char::is_whitespace) {
+        let after = rest[start..].trim_start();
+        let is_between_tags =
+            rest[..start].ends_with('>') || after.starts_with('<') || after.is_empty();
+        collapsed.push_str(&rest[..start]);
+        if !is_between_tags {
+            collapsed.push_str(&rest[start..rest.len() - after.len()]);
+        }
+        rest = after;
+    }
+    collapsed.push_str(rest);
+    collapsed
+}
+
+/// The lines of `s` without their indentation.
+fn unindented(s: &str) -> impl Iterator<Item = &str> {
+    s.split('\n')
+        .map(|line| line.trim_start_matches([' ', '\t']))
+}
+
+/// Where two texts first differ, as found by [`first_difference`].
+#[derive(Debug)]
+pub struct LineDifference<'a> {
+    /// The line, numbered from 1.
+    pub line: usize,
+    /// The byte offset of the first differing character within the line.
+    pub column: usize,
+    pub actual: &'a str,
+    pub expected: &'a str,
+}
+
+/// Lines longer than this are shown as an excerpt around the difference.
+const MAX_EXCERPT: usize = 120;
+
+impl LineDifference<'_> {
+    /// `line`, or for a long one the part of it around the difference, marked with `…`.
+    pub fn excerpt(&self, line: &str) -> String {
+        if line.len() <= MAX_EXCERPT {
+            return line.to_string();
+        }
+
+        let floor = |mut i: usize| {
+            i = i.min(line.len());
+            while !line.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        };
+        let start = floor(self.column.saturating_sub(MAX_EXCERPT / 2));
+        let end = floor(start + MAX_EXCERPT);
+        format!(
+            "{}{}{}",
+            if start > 0 { "…" } else { "" },
+            &line[start..end],
+            if end < line.len() { "…" } else { "" }
+        )
+    }
+}
+
+/// The first line at which `actual` and `expected` differ, with the two versions of it. This is
+/// a single scan for the first differing byte, so it stays cheap when a line is megabytes long.
+pub fn first_difference<'a>(actual: &'a str, expected: &'a str) -> Option<LineDifference<'a>> {
+    let mut offset = actual
+        .bytes()
+        .zip(expected.bytes())
+        .position(|(a, e)| a != e)
+        .unwrap_or(actual.len().min(expected.len()));
+    if offset == actual.len() && offset == expected.len() {
+        return None;
+    }
+    while !actual.is_char_boundary(offset) {
+        offset -= 1;
+    }
+
+    let line_start = actual[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_of = |s: &'a str| {
+        let rest = &s[line_start..];
+        &rest[..rest.find('\n').unwrap_or(rest.len())]
+    };
+
+    Some(LineDifference {
+        line: actual[..line_start].matches('\n').count() + 1,
+        column: offset - line_start,
+        actual: line_of(actual),
+        expected: line_of(expected),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FORMATTED: &str = "<r>\n  <a x=\"1\" y=\"2\">text</a>\n</r>\n";
+
+    #[test]
+    fn classifies_nothing_for_equal_documents() {
+        assert!(classify(FORMATTED, FORMATTED).is_empty());
+    }
+
+    #[test]
+    fn classifies_a_byte_order_mark() {
+        let original = format!("\u{feff}{}", FORMATTED);
+        assert_eq!(classify(FORMATTED, &original), ["bom"]);
+        // Output that keeps it does not differ in it.
+        assert!(classify(&original, &original).is_empty());
+    }
+
+    #[test]
+    fn classifies_line_endings() {
+        let original = FORMATTED.replace('\n', "\r\n");
+        assert_eq!(classify(FORMATTED, &original), ["line-endings"]);
+        assert!(classify(&original, &original).is_empty());
+    }
+
+    #[test]
+    fn classifies_trailing_whitespace() {
+        let original = FORMATTED.replace("</a>", "</a>  \t");
+        assert_eq!(classify(FORMATTED, &original), ["trailing-whitespace"]);
+    }
+
+    #[test]
+    fn classifies_the_final_newline() {
+        assert_eq!(classify(FORMATTED, FORMATTED.trim_end()), ["final-newline"]);
+        assert_eq!(
+            classify(FORMATTED, &format!("{}\n", FORMATTED)),
+            ["final-newline"]
+        );
+    }
+
+    #[test]
+    fn classifies_indentation() {
+        let original = FORMATTED.replace("  <a", "\t<a");
+        assert_eq!(classify(FORMATTED, &original), ["indentation"]);
+    }
+
+    #[test]
+    fn classifies_a_formatted_minified_document_as_indentation() {
+        let original = "<r><a x=\"1\" y=\"2\">text</a></r>";
+        assert_eq!(
+            classify(FORMATTED, original),
+            ["final-newline", "indentation"]
+        );
+        assert_eq!(
+            classify(
+                FORMATTED,
+                "<r>\n  <a x=\"1\" y=\"2\">\n    text\n  </a>\n</r>\n"
+            ),
+            ["indentation"]
+        );
+        // Whitespace within text is kept.
+        assert_eq!(
+            classify(FORMATTED, "<r><a x=\"1\" y=\"2\">te xt</a></r>"),
+            ["final-newline", "other-structural"]
+        );
+    }
+
+    #[test]
+    fn classifies_attribute_wrapping() {
+        let original = FORMATTED.replace(" y=", "\n     y=");
+        assert_eq!(classify(FORMATTED, &original), ["attribute-wrapping"]);
+    }
+
+    #[test]
+    fn classifies_attribute_order_as_structural() {
+        let original = FORMATTED.replace("x=\"1\" y=\"2\"", "y=\"2\" x=\"1\"");
+        assert_eq!(classify(FORMATTED, &original), ["other-structural"]);
+    }
+
+    #[test]
+    fn classifies_quoting_as_structural() {
+        let original = FORMATTED.replace("x=\"1\"", "x='1'");
+        assert_eq!(classify(FORMATTED, &original), ["other-structural"]);
+    }
+
+    #[test]
+    fn classifies_content_as_structural() {
+        let original = FORMATTED.replace("text", "other text");
+        assert_eq!(classify(FORMATTED, &original), ["other-structural"]);
+    }
+
+    #[test]
+    fn combines_categories_in_a_fixed_order() {
+        let original =
+            format!("\u{feff}{}", FORMATTED.replace("  <a", "    <a")).replace('\n', "  \r\n");
+        assert_eq!(
+            classify(FORMATTED, &original),
+            ["bom", "line-endings", "trailing-whitespace", "indentation"]
+        );
+    }
+
+    #[test]
+    fn finds_the_first_difference() {
+        let difference = first_difference("<r>\n  <a/>\n</r>", "<r>\n  <b/>\n</r>").unwrap();
+        assert_eq!((difference.line, difference.column), (2, 3));
+        assert_eq!(
+            (difference.actual, difference.expected),
+            ("  <a/>", "  <b/>")
+        );
+        assert!(first_difference("same", "same").is_none());
+    }
+
+    #[test]
+    fn finds_a_difference_at_the_end() {
+        let difference = first_difference("<r/>", "<r/>\n").unwrap();
+        assert_eq!((difference.line, difference.column), (1, 4));
+    }
+
+    #[test]
+    fn first_difference_stays_on_a_character_boundary() {
+        // é and è share their first byte, so the first differing byte is inside a character.
+        let difference = first_difference("<a>é</a>", "<a>è</a>").unwrap();
+        assert_eq!(difference.column, 3);
+        assert_eq!(
+            (difference.actual, difference.expected),
+            ("<a>é</a>", "<a>è</a>")
+        );
+    }
+
+    #[test]
+    fn excerpts_long_lines_around_the_difference() {
+        let line = format!("{}X{}", "a".repeat(200), "b".repeat(200));
+        let difference = LineDifference {
+            line: 1,
+            column: 200,
+            actual: &line,
+            expected: &line,
+        };
+        let excerpt = difference.excerpt(&line);
+        assert_eq!(excerpt, format!("…{}X{}…", "a".repeat(60), "b".repeat(59)));
+        assert_eq!(difference.excerpt("short"), "short");
+    }
+
+    #[test]
+    fn excerpts_cut_on_character_boundaries() {
+        let line = "é".repeat(200);
+        for column in [0, 1, 2, 61, 121, 399, 400] {
+            let difference = LineDifference {
+                line: 1,
+                column,
+                actual: &line,
+                expected: &line,
+            };
+            let excerpt = difference.excerpt(&line);
+            assert!(excerpt.trim_matches('…').chars().all(|ch| ch == 'é'));
+            assert!(excerpt.trim_matches('…').len() <= MAX_EXCERPT);
+        }
+    }
+
+    #[test]
+    fn normalizes_line_endings_and_trailing_space() {
+        let comparison = Comparison {
+            is_newline_insensitive: true,
+            is_trailing_space_insensitive: true,
+        };
+        assert_eq!(comparison.normalize("a  \r\nb\t\rc"), "a\nb\nc");
+        assert!(comparison.is_equal("a\nb\n", "a \r\nb\r\n"));
+        assert!(!Comparison::default().is_equal("a\nb\n", "a \r\nb\r\n"));
+    }
+}
@@ -1,34 +1,330 @@
+mod attributes;
+mod audit;
+mod characters;
+mod children;
+mod comments;
+mod diff_tool;
+mod dump;
+mod encoding;
+mod equivalence;
+mod files;
+mod gzip;
+mod lint;
+mod markup;
+mod namespaces;
+mod numbers;
+mod outline;
+mod progress;
+mod reindent;
+mod summary;
+mod timings;
+
 use std::{
+    env,
     fs::{write, File},
-    io::{self, IsTerminal, Read, StdinLock},
+    io::{self, IsTerminal, Read, Write},
+    mem::ManuallyDrop,
     path::{Path, PathBuf},
+    process::exit,
     str::FromStr,
 };
 
 use anyhow::Context;
 use gumdrop::Options;
-use xmlem::{display, Document};
+use xmlem::{display, Declaration, Document};
+
+use crate::{
+    attributes::{CaseRule, SortExemptions, XmlnsSort},
+    audit::AuditLog,
+    characters::InvalidCharPolicy,
+    children::{Parents, SortKey},
+    files::PathStyle,
+    lint::Comparison,
+    markup::EmptyDocument,
+    namespaces::UndeclaredPrefixPolicy,
+    numbers::Rounding,
+    outline::TooDeep,
+    progress::Progress,
+    summary::Summary,
+    timings::Timings,
+};
+
+#[derive(Debug, Options)]
+struct Cli {
+    #[options(help = "display help information")]
+    help: bool,
+
+    #[options(command)]
+    command: Option<Command>,
+}
 
 #[derive(Debug, Options)]
-struct Args {
+enum Command {
+    #[options(help = "format XML documents (the default when no command is given)")]
+    Format(FormatArgs),
+
+    #[options(help = "check that XML documents are already formatted, like format --lint")]
+    Check(CheckArgs),
+
+    #[options(help = "write XML documents without indentation or line breaks")]
+    Minify(MinifyArgs),
+
+    #[options(help = "report whether two XML documents are equivalent")]
+    Diff(DiffArgs),
+
+    #[options(help = "print the path of each element instead of formatting")]
+    Tree(TreeArgs),
+}
+
+/// Formats XML documents, or checks that they are already formatted. Running xml-pretty
+/// without a command is the same as running `xml-pretty format`.
+#[derive(Debug, Default, Clone, Options)]
+struct FormatArgs {
     #[options(help = "display help information")]
     help: bool,
 
-    #[options(free, help = "path to XML document")]
-    xml_document_path: Option<PathBuf>,
+    #[options(free, help = "paths to XML documents or directories")]
+    xml_document_paths: Vec<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "ext",
+        meta = "EXT,...",
+        help = "file extensions to look for in directories (default: xml)"
+    )]
+    extensions: Option<String>,
+
+    #[options(
+        no_short,
+        long = "detect-xml",
+        help = "also take files from directories whose content looks like XML"
+    )]
+    is_detect_xml: bool,
+
+    #[options(short = "v", long = "verbose", help = "report each document processed")]
+    is_verbose: bool,
+
+    #[options(
+        no_short,
+        long = "progress",
+        help = "show how many documents are done when stderr is a terminal"
+    )]
+    is_progress: bool,
+
+    #[options(
+        no_short,
+        long = "fail-fast",
+        help = "stop at the first document that fails instead of processing the rest"
+    )]
+    is_fail_fast: bool,
 
     #[options(help = "output to file")]
     output_path: Option<PathBuf>,
 
+    #[options(
+        no_short,
+        long = "stdin",
+        help = "read the document from stdin, e.g. to format it into -o PATH"
+    )]
+    is_stdin: bool,
+
+    #[options(
+        no_short,
+        meta = "FD",
+        help = "read the document from an already open file descriptor (Unix only)"
+    )]
+    input_fd: Option<i32>,
+
+    #[options(
+        no_short,
+        meta = "FD",
+        help = "write the output to an already open file descriptor (Unix only)"
+    )]
+    output_fd: Option<i32>,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "name to show in messages for a document read from stdin or --input-fd"
+    )]
+    stdin_filepath: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        meta = "TEMPLATE",
+        help = "write a comment before each document on stdout, with {path} substituted"
+    )]
+    stdout_header: Option<String>,
+
     #[options(short = "r", long = "replace", help = "replace input file with output")]
     is_replace: bool,
 
     #[options(short = "c", long = "lint", help = "lint document without formatting")]
     lint_mode: bool,
 
+    #[options(
+        no_short,
+        long = "newline-insensitive",
+        help = "ignore line ending differences when linting"
+    )]
+    is_newline_insensitive: bool,
+
+    #[options(
+        no_short,
+        long = "trailing-space-insensitive",
+        help = "ignore trailing spaces and tabs when linting"
+    )]
+    is_trailing_space_insensitive: bool,
+
+    #[options(
+        no_short,
+        meta = "COMMAND",
+        help = "when linting fails, run COMMAND with {old} and {new} as files of the original and formatted document"
+    )]
+    diff_tool: Option<String>,
+
+    #[options(
+        no_short,
+        long = "sort-attributes",
+        help = "sort attributes by name, namespace declarations first"
+    )]
+    is_sort_attributes: bool,
+
+    #[options(
+        no_short,
+        meta = "NAME,...",
+        help = "keep the attribute order of these elements; NAME/** also covers their descendants"
+    )]
+    no_sort_attributes_in: Option<String>,
+
+    #[options(
+        no_short,
+        long = "strip-attr",
+        meta = "NAME",
+        help = "remove attributes named NAME, or starting with NAME if it ends in *; repeatable"
+    )]
+    strip_attributes: Vec<String>,
+
+    #[options(
+        no_short,
+        long = "expand-boolean-attr",
+        meta = "NAME",
+        help = "write an empty NAME=\"\" as NAME=\"NAME\", as XHTML does; repeatable"
+    )]
+    boolean_attributes: Vec<String>,
+
+    #[options(
+        no_short,
+        long = "normalize-attr-case",
+        meta = "NAME=lower|upper",
+        help = "lower- or upper-case the values of attribute NAME; checked when linting; repeatable"
+    )]
+    case_rules: Vec<CaseRule>,
+
+    #[options(
+        no_short,
+        meta = "PRECISION[:attrs=NAME,...]",
+        help = "round numbers in SVG geometry attributes, or those listed, to PRECISION decimals"
+    )]
+    round_numbers: Option<Rounding>,
+
+    #[options(
+        no_short,
+        long = "check-sorted-attributes",
+        help = "when linting, fail if attributes are not in --sort-attributes order"
+    )]
+    is_check_sorted_attributes: bool,
+
+    #[options(
+        no_short,
+        meta = "SELECTOR",
+        help = "when linting, fail if children of matching elements are not in --sort-children order"
+    )]
+    check_sorted_children: Option<Parents>,
+
+    #[options(
+        no_short,
+        meta = "prefix|uri|none",
+        help = "order of sorted namespace declarations (default: prefix)"
+    )]
+    xmlns_sort: Option<XmlnsSort>,
+
+    #[options(
+        no_short,
+        meta = "SELECTOR",
+        help = "sort the child elements of elements matching the CSS selector"
+    )]
+    sort_children: Option<Parents>,
+
+    #[options(
+        no_short,
+        meta = "name|@ATTR|key=CHILD[/CHILD...][:icase]",
+        help = "what --sort-children orders by: element name, an attribute or child element text (default: name)"
+    )]
+    sort_children_by: Option<SortKey>,
+
+    #[options(
+        no_short,
+        meta = "error|allow|synthesize",
+        help = "handling of namespace prefixes without a declaration (default: error)"
+    )]
+    undeclared_prefix: Option<UndeclaredPrefixPolicy>,
+
+    #[options(
+        no_short,
+        meta = "error|strip|entity",
+        help = "handling of control characters and others XML does not allow (default: error)"
+    )]
+    invalid_chars: Option<InvalidCharPolicy>,
+
+    #[options(
+        no_short,
+        meta = "URI",
+        help = "with --lint, fail on elements in no namespace when the root's default namespace is URI"
+    )]
+    check_default_namespace: Option<String>,
+
+    #[options(
+        no_short,
+        long = "fix-default-namespace",
+        help = "remove xmlns=\"\" overrides that take elements out of the --check-default-namespace URI"
+    )]
+    is_fix_default_namespace: bool,
+
+    #[options(
+        no_short,
+        long = "fix-encoding-declaration",
+        help = "rewrite the declared encoding to match the output (UTF-8)"
+    )]
+    is_fix_encoding_declaration: bool,
+
+    #[options(
+        no_short,
+        long = "omit-utf8-encoding",
+        help = "leave encoding out of the XML declaration when it is UTF-8"
+    )]
+    is_omit_utf8_encoding: bool,
+
     #[options(help = "number of spaces to indent (default: 2)")]
     indent: Option<usize>,
 
+    #[options(no_short, help = "same as --indent, as in prettier")]
+    indent_size: Option<usize>,
+
+    #[options(
+        no_short,
+        meta = "STYLE",
+        help = "indent with 'space' (the default), or 'tab' with --reindent-only"
+    )]
+    indent_style: Option<IndentStyle>,
+
+    #[options(
+        no_short,
+        long = "reindent-only",
+        help = "only change the indentation at the start of lines, leaving the rest as written"
+    )]
+    is_reindent_only: bool,
+
     #[options(
         short = "e",
         help = "number of spaces to pad the end of an element without separate end-tag (default: 1)"
@@ -38,6 +334,9 @@ struct Args {
     #[options(short = "l", help = "max line length (default: 120)")]
     max_line_length: Option<usize>,
 
+    #[options(no_short, help = "same as --max-line-length, as in prettier")]
+    print_width: Option<usize>,
+
     #[options(
         short = "H",
         long = "hex-entities",
@@ -45,156 +344,2231 @@ struct Args {
     )]
     uses_hex_entities: bool,
 
+    #[options(
+        no_short,
+        meta = "TOOL",
+        help = "match another formatter's output where xmlem can: 'xmllint' for xmllint --format"
+    )]
+    compat: Option<Compat>,
+
     #[options(
         no_short,
         long = "no-text-indent",
         help = "Do not prettify and indent text nodes"
     )]
     is_no_text_indent: bool,
-}
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse_args_default_or_exit();
+    #[options(
+        no_short,
+        meta = "yes|no",
+        help = "separate the prolog from the root element with a blank line (default: yes, or no with --compat xmllint)"
+    )]
+    prolog_blank_line: Option<YesNo>,
 
-    let input_path = if let Some(path) = args.xml_document_path {
-        Some(path)
-    } else if io::stdin().is_terminal() {
-        eprintln!("ERROR: No XML document provided.");
-        eprintln!("Run with -h for usage information.");
-        return Ok(());
-    } else {
-        None
-    };
+    #[options(
+        no_short,
+        long = "wrap-comments",
+        help = "break comment lines longer than the max line length at spaces"
+    )]
+    is_wrap_comments: bool,
 
-    let output_path = if args.is_replace {
-        if let Some(input_path) = input_path.as_ref() {
-            Some(input_path.clone())
-        } else {
-            eprintln!("ERROR: cannot replace 'file' when provided stdin data.");
-            return Ok(());
-        }
-    } else {
-        args.output_path
-    };
+    #[options(
+        no_short,
+        meta = "N",
+        help = "with --wrap-comments, wrap at N columns after the comment's indentation instead"
+    )]
+    comment_width: Option<usize>,
 
-    let (formatted, original) = if let Some(ref input_path) = input_path {
-        prettify_file(
-            input_path,
-            args.indent,
-            args.end_pad,
-            args.max_line_length,
-            args.uses_hex_entities,
-            !args.is_no_text_indent,
-        )
-        .with_context(|| format!("Failed to prettify '{}'", input_path.display()))?
-    } else {
-        let stdin = std::io::stdin();
-        let stdin = stdin.lock();
-        prettify_stdin(
-            stdin,
-            args.indent,
-            args.end_pad,
-            args.max_line_length,
-            args.uses_hex_entities,
-            !args.is_no_text_indent,
-        )
-        .context("Failed to prettify from stdin")?
-    };
+    #[options(
+        no_short,
+        long = "no-wrap-root",
+        help = "keep the root element's start tag on one line, however long"
+    )]
+    is_no_wrap_root: bool,
 
-    if args.lint_mode {
-        if formatted == original {
-            return Ok(());
-        } else {
-            return Err(anyhow::anyhow!(
-                "xml-pretty --lint failed for document {}",
-                if input_path.is_some() {
-                    format!("at path: `{}`", input_path.as_ref().unwrap().display())
-                } else {
-                    "from stdin".to_string()
-                }
-            ));
-        }
-    }
+    #[options(
+        no_short,
+        meta = "relative|absolute|as-given",
+        help = "how to show document paths in messages (default: relative)"
+    )]
+    path_style: Option<PathStyle>,
 
-    if let Some(path) = output_path {
-        write(&path, formatted)
-            .with_context(|| format!("Failed to write to '{}'", path.display()))?;
-    } else {
-        println!("{}", formatted);
-    }
+    #[options(
+        no_short,
+        long = "allow-empty",
+        help = "treat an empty document as valid, producing empty output"
+    )]
+    is_allow_empty: bool,
 
-    Ok(())
-}
+    #[options(
+        no_short,
+        long = "fragment",
+        help = "accept several top-level elements and text, as in a fragment of a document"
+    )]
+    is_fragment: bool,
 
-fn prettify_file(
-    path: &Path,
-    indent: Option<usize>,
-    end_pad: Option<usize>,
-    max_line_length: Option<usize>,
-    uses_hex_entities: bool,
-    indent_text_nodes: bool,
-) -> anyhow::Result<(String, String)> {
-    let file = File::open(path)?;
-    let contents = std::fs::read_to_string(path)
-        .with_context(|| format!("Failed to read file '{}'", path.display()))?;
-    let doc = Document::from_file(file)?;
-    Ok((
-        prettify(
-            doc,
-            indent,
-            end_pad,
-            max_line_length,
-            uses_hex_entities,
-            indent_text_nodes,
-        ),
-        contents,
-    ))
-}
+    #[options(no_short, help = "print time spent in each phase to stderr")]
+    timings: bool,
 
-fn prettify_stdin(
-    mut stdin: StdinLock,
-    indent: Option<usize>,
-    end_pad: Option<usize>,
-    max_line_length: Option<usize>,
-    uses_hex_entities: bool,
-    indent_text_nodes: bool,
-) -> anyhow::Result<(String, String)> {
-    let mut buffer = String::new();
-    stdin
-        .read_to_string(&mut buffer)
-        .context("Failed to read from stdin")?;
-    let doc = Document::from_str(&buffer)?;
-    Ok((
-        prettify(
-            doc,
-            indent,
-            end_pad,
-            max_line_length,
-            uses_hex_entities,
-            indent_text_nodes,
-        ),
-        buffer,
-    ))
+    #[options(
+        no_short,
+        meta = "DIR",
+        help = "compare each formatted document with the file of the same name in DIR"
+    )]
+    snapshot_dir: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "append a JSON line to PATH for each document, recording what was done to it"
+    )]
+    log_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "when done, write a JSON summary of the run to PATH, or to stderr if PATH is -"
+    )]
+    summary_json: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "accept",
+        help = "with --snapshot-dir, write the formatted documents to DIR instead of comparing"
+    )]
+    is_accept: bool,
+
+    #[options(
+        no_short,
+        long = "dump-tree",
+        help = "print the parsed document as a list of nodes instead of formatting it"
+    )]
+    is_dump_tree: bool,
 }
 
-fn prettify(
-    doc: Document,
-    indent: Option<usize>,
-    end_pad: Option<usize>,
-    max_line_length: Option<usize>,
-    uses_hex_entities: bool,
-    indent_text_nodes: bool,
-) -> String {
-    doc.to_string_pretty_with_config(
-        &display::Config::default_pretty()
-            .indent(indent.unwrap_or(2))
-            .end_pad(end_pad.unwrap_or(1))
-            .max_line_length(max_line_length.unwrap_or(120))
-            .entity_mode(if uses_hex_entities {
-                display::EntityMode::Hex
-            } else {
-                display::EntityMode::Standard
-            })
-            .indent_text_nodes(indent_text_nodes),
-    )
+/// Checks that XML documents are already formatted, without changing them. A document that
+/// would change is reported, with where it first differs, and the run fails.
+#[derive(Debug, Options)]
+struct CheckArgs {
+    #[options(help = "display help information")]
+    help: bool,
+
+    #[options(free, help = "paths to XML documents or directories")]
+    xml_document_paths: Vec<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "ext",
+        meta = "EXT,...",
+        help = "file extensions to look for in directories (default: xml)"
+    )]
+    extensions: Option<String>,
+
+    #[options(
+        no_short,
+        long = "detect-xml",
+        help = "also take files from directories whose content looks like XML"
+    )]
+    is_detect_xml: bool,
+
+    #[options(short = "v", long = "verbose", help = "report each document processed")]
+    is_verbose: bool,
+
+    #[options(
+        no_short,
+        long = "progress",
+        help = "show how many documents are done when stderr is a terminal"
+    )]
+    is_progress: bool,
+
+    #[options(
+        no_short,
+        long = "fail-fast",
+        help = "stop at the first document that fails instead of processing the rest"
+    )]
+    is_fail_fast: bool,
+
+    #[options(no_short, long = "stdin", help = "read the document from stdin")]
+    is_stdin: bool,
+
+    #[options(
+        no_short,
+        meta = "FD",
+        help = "read the document from an already open file descriptor (Unix only)"
+    )]
+    input_fd: Option<i32>,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "name to show in messages for a document read from stdin or --input-fd"
+    )]
+    stdin_filepath: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "newline-insensitive",
+        help = "ignore line ending differences when linting"
+    )]
+    is_newline_insensitive: bool,
+
+    #[options(
+        no_short,
+        long = "trailing-space-insensitive",
+        help = "ignore trailing spaces and tabs when linting"
+    )]
+    is_trailing_space_insensitive: bool,
+
+    #[options(
+        no_short,
+        meta = "COMMAND",
+        help = "when linting fails, run COMMAND with {old} and {new} as files of the original and formatted document"
+    )]
+    diff_tool: Option<String>,
+
+    #[options(
+        no_short,
+        long = "sort-attributes",
+        help = "sort attributes by name, namespace declarations first"
+    )]
+    is_sort_attributes: bool,
+
+    #[options(
+        no_short,
+        meta = "NAME,...",
+        help = "keep the attribute order of these elements; NAME/** also covers their descendants"
+    )]
+    no_sort_attributes_in: Option<String>,
+
+    #[options(
+        no_short,
+        long = "strip-attr",
+        meta = "NAME",
+        help = "remove attributes named NAME, or starting with NAME if it ends in *; repeatable"
+    )]
+    strip_attributes: Vec<String>,
+
+    #[options(
+        no_short,
+        long = "expand-boolean-attr",
+        meta = "NAME",
+        help = "write an empty NAME=\"\" as NAME=\"NAME\", as XHTML does; repeatable"
+    )]
+    boolean_attributes: Vec<String>,
+
+    #[options(
+        no_short,
+        long = "normalize-attr-case",
+        meta = "NAME=lower|upper",
+        help = "lower- or upper-case the values of attribute NAME; checked when linting; repeatable"
+    )]
+    case_rules: Vec<CaseRule>,
+
+    #[options(
+        no_short,
+        meta = "PRECISION[:attrs=NAME,...]",
+        help = "round numbers in SVG geometry attributes, or those listed, to PRECISION decimals"
+    )]
+    round_numbers: Option<Rounding>,
+
+    #[options(
+        no_short,
+        long = "check-sorted-attributes",
+        help = "when linting, fail if attributes are not in --sort-attributes order"
+    )]
+    is_check_sorted_attributes: bool,
+
+    #[options(
+        no_short,
+        meta = "SELECTOR",
+        help = "when linting, fail if children of matching elements are not in --sort-children order"
+    )]
+    check_sorted_children: Option<Parents>,
+
+    #[options(
+        no_short,
+        meta = "prefix|uri|none",
+        help = "order of sorted namespace declarations (default: prefix)"
+    )]
+    xmlns_sort: Option<XmlnsSort>,
+
+    #[options(
+        no_short,
+        meta = "SELECTOR",
+        help = "sort the child elements of elements matching the CSS selector"
+    )]
+    sort_children: Option<Parents>,
+
+    #[options(
+        no_short,
+        meta = "name|@ATTR|key=CHILD[/CHILD...][:icase]",
+        help = "what --sort-children orders by: element name, an attribute or child element text (default: name)"
+    )]
+    sort_children_by: Option<SortKey>,
+
+    #[options(
+        no_short,
+        meta = "error|allow|synthesize",
+        help = "handling of namespace prefixes without a declaration (default: error)"
+    )]
+    undeclared_prefix: Option<UndeclaredPrefixPolicy>,
+
+    #[options(
+        no_short,
+        meta = "error|strip|entity",
+        help = "handling of control characters and others XML does not allow (default: error)"
+    )]
+    invalid_chars: Option<InvalidCharPolicy>,
+
+    #[options(
+        no_short,
+        meta = "URI",
+        help = "fail on elements in no namespace when the root's default namespace is URI"
+    )]
+    check_default_namespace: Option<String>,
+
+    #[options(
+        no_short,
+        long = "fix-default-namespace",
+        help = "remove xmlns=\"\" overrides that take elements out of the --check-default-namespace URI"
+    )]
+    is_fix_default_namespace: bool,
+
+    #[options(
+        no_short,
+        long = "fix-encoding-declaration",
+        help = "rewrite the declared encoding to match the output (UTF-8)"
+    )]
+    is_fix_encoding_declaration: bool,
+
+    #[options(
+        no_short,
+        long = "omit-utf8-encoding",
+        help = "leave encoding out of the XML declaration when it is UTF-8"
+    )]
+    is_omit_utf8_encoding: bool,
+
+    #[options(help = "number of spaces to indent (default: 2)")]
+    indent: Option<usize>,
+
+    #[options(no_short, help = "same as --indent, as in prettier")]
+    indent_size: Option<usize>,
+
+    #[options(
+        no_short,
+        meta = "STYLE",
+        help = "indent with 'space' (the default), or 'tab' with --reindent-only"
+    )]
+    indent_style: Option<IndentStyle>,
+
+    #[options(
+        no_short,
+        long = "reindent-only",
+        help = "only change the indentation at the start of lines, leaving the rest as written"
+    )]
+    is_reindent_only: bool,
+
+    #[options(
+        short = "e",
+        help = "number of spaces to pad the end of an element without separate end-tag (default: 1)"
+    )]
+    end_pad: Option<usize>,
+
+    #[options(short = "l", help = "max line length (default: 120)")]
+    max_line_length: Option<usize>,
+
+    #[options(no_short, help = "same as --max-line-length, as in prettier")]
+    print_width: Option<usize>,
+
+    #[options(
+        short = "H",
+        long = "hex-entities",
+        help = "Use hex entity encoding (e.g. &#xNNNN;) for all entities"
+    )]
+    uses_hex_entities: bool,
+
+    #[options(
+        no_short,
+        meta = "TOOL",
+        help = "match another formatter's output where xmlem can: 'xmllint' for xmllint --format"
+    )]
+    compat: Option<Compat>,
+
+    #[options(
+        no_short,
+        long = "no-text-indent",
+        help = "Do not prettify and indent text nodes"
+    )]
+    is_no_text_indent: bool,
+
+    #[options(
+        no_short,
+        meta = "yes|no",
+        help = "separate the prolog from the root element with a blank line (default: yes, or no with --compat xmllint)"
+    )]
+    prolog_blank_line: Option<YesNo>,
+
+    #[options(
+        no_short,
+        long = "wrap-comments",
+        help = "break comment lines longer than the max line length at spaces"
+    )]
+    is_wrap_comments: bool,
+
+    #[options(
+        no_short,
+        meta = "N",
+        help = "with --wrap-comments, wrap at N columns after the comment's indentation instead"
+    )]
+    comment_width: Option<usize>,
+
+    #[options(
+        no_short,
+        long = "no-wrap-root",
+        help = "keep the root element's start tag on one line, however long"
+    )]
+    is_no_wrap_root: bool,
+
+    #[options(
+        no_short,
+        meta = "relative|absolute|as-given",
+        help = "how to show document paths in messages (default: relative)"
+    )]
+    path_style: Option<PathStyle>,
+
+    #[options(
+        no_short,
+        long = "allow-empty",
+        help = "treat an empty document as valid, producing empty output"
+    )]
+    is_allow_empty: bool,
+
+    #[options(
+        no_short,
+        long = "fragment",
+        help = "accept several top-level elements and text, as in a fragment of a document"
+    )]
+    is_fragment: bool,
+
+    #[options(no_short, help = "print time spent in each phase to stderr")]
+    timings: bool,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "append a JSON line to PATH for each document, recording what was done to it"
+    )]
+    log_file: Option<PathBuf>,
+
+    #[options(
+        no_short,
+        meta = "PATH",
+        help = "when done, write a JSON summary of the run to PATH, or to stderr if PATH is -"
+    )]
+    summary_json: Option<PathBuf>,
+}
+
+/// Writes XML documents without indentation or line breaks between elements.
+#[derive(Debug, Options)]
+struct MinifyArgs {
+    #[options(help = "display help information")]
+    help: bool,
+
+    #[options(free, help = "paths to XML documents or directories")]
+    xml_document_paths: Vec<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "ext",
+        meta = "EXT,...",
+        help = "file extensions to look for in directories (default: xml)"
+    )]
+    extensions: Option<String>,
+
+    #[options(
+        no_short,
+        long = "detect-xml",
+        help = "also take files from directories whose content looks like XML"
+    )]
+    is_detect_xml: bool,
+
+    #[options(short = "v", long = "verbose", help = "report each document processed")]
+    is_verbose: bool,
+
+    #[options(help = "output to file")]
+    output_path: Option<PathBuf>,
+
+    #[options(short = "r", long = "replace", help = "replace input file with output")]
+    is_replace: bool,
+}
+
+/// Prints the path of each element, one per line, instead of the document itself.
+#[derive(Debug, Options)]
+struct TreeArgs {
+    #[options(help = "display help information")]
+    help: bool,
+
+    #[options(free, help = "paths to XML documents or directories")]
+    xml_document_paths: Vec<PathBuf>,
+
+    #[options(
+        no_short,
+        long = "ext",
+        meta = "EXT,...",
+        help = "file extensions to look for in directories (default: xml)"
+    )]
+    extensions: Option<String>,
+
+    #[options(
+        no_short,
+        long = "detect-xml",
+        help = "also take files from directories whose content looks like XML"
+    )]
+    is_detect_xml: bool,
+
+    #[options(short = "v", long = "verbose", help = "report each document processed")]
+    is_verbose: bool,
+
+    #[options(no_short, meta = "N", help = "only show elements up to N levels deep")]
+    depth: Option<usize>,
+
+    #[options(no_short, help = "include attributes")]
+    attrs: bool,
+}
+
+/// Reports whether two XML documents are equivalent, ignoring insignificant whitespace,
+/// attribute order and quote style.
+#[derive(Debug, Options)]
+struct DiffArgs {
+    #[options(help = "display help information")]
+    help: bool,
+
+    #[options(free, help = "paths to the two XML documents to compare")]
+    paths: Vec<PathBuf>,
+}
+
+/// What to do with each document once it has been loaded.
+#[derive(Debug, Clone, Copy)]
+enum Mode {
+    Format,
+    Check,
+    Minify,
+    Tree {
+        max_depth: Option<usize>,
+        include_attrs: bool,
+    },
+    Dump,
+}
+
+impl Mode {
+    /// Describes a finished document, as in `formatted 3/10`.
+    fn verb(self) -> &'static str {
+        match self {
+            Mode::Format => "formatted",
+            Mode::Check => "checked",
+            Mode::Minify => "minified",
+            Mode::Tree { .. } | Mode::Dump => "listed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum YesNo {
+    Yes,
+    No,
+}
+
+impl FromStr for YesNo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "yes" => Ok(YesNo::Yes),
+            "no" => Ok(YesNo::No),
+            other => Err(format!("expected 'yes' or 'no', got '{}'", other)),
+        }
+    }
+}
+
+/// The character lines are indented with, for `--indent-style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentStyle {
+    Space,
+    Tab,
+}
+
+impl FromStr for IndentStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "space" => Ok(IndentStyle::Space),
+            "tab" => Ok(IndentStyle::Tab),
+            other => Err(format!("expected 'space' or 'tab', got '{}'", other)),
+        }
+    }
+}
+
+/// Another formatter whose output `--compat` imitates, by changing the defaults of the other
+/// formatting flags. Flags given explicitly still win.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compat {
+    /// `xmllint --format`: text stays inline, attributes never wrap, empty elements are written
+    /// as `<e/>` and a missing declaration is added.
+    Xmllint,
+}
+
+impl FromStr for Compat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "xmllint" => Ok(Compat::Xmllint),
+            other => Err(format!("expected 'xmllint', got '{}'", other)),
+        }
+    }
+}
+
+/// A parsed document together with what is needed to lint it and write it back.
+struct Loaded {
+    doc: Document,
+    /// The source text, decompressed if necessary.
+    original: String,
+    is_gzip: bool,
+    encoding_mismatch: Option<encoding::Mismatch>,
+    /// False for a prolog-only document, which is parsed with a placeholder root element.
+    has_root: bool,
+    /// Invalid characters and stray byte order marks, let through by `--invalid-chars`.
+    characters: characters::Findings,
+}
+
+/// Everything needed to turn a parsed document into its formatted text.
+struct Formatting {
+    config: display::Config,
+    indent: usize,
+    max_line_length: usize,
+    prolog_blank_line: bool,
+    is_no_wrap_root: bool,
+    /// Write attribute values the way `xmllint --format` does, for `--compat xmllint`.
+    is_xmllint_attributes: bool,
+}
+
+/// xmlem pads with `format!` widths, which cannot be wider than this.
+const MAX_PADDING: usize = u16::MAX as usize;
+
+impl FormatArgs {
+    fn formatting(&self) -> Formatting {
+        let is_xmllint = self.compat == Some(Compat::Xmllint);
+        let indent = self.indent.or(self.indent_size).unwrap_or(2);
+        let max_line_length = self
+            .max_line_length
+            .or(self.print_width)
+            .unwrap_or(if is_xmllint { usize::MAX } else { 120 });
+        Formatting {
+            config: display::Config::default_pretty()
+                .indent(indent)
+                .end_pad(self.end_pad.unwrap_or(if is_xmllint { 0 } else { 1 }))
+                .max_line_length(max_line_length)
+                .entity_mode(if self.uses_hex_entities {
+                    display::EntityMode::Hex
+                } else {
+                    display::EntityMode::Standard
+                })
+                .indent_text_nodes(!self.is_no_text_indent && !is_xmllint),
+            indent,
+            max_line_length,
+            prolog_blank_line: self
+                .prolog_blank_line
+                .map_or(!is_xmllint, |given| given == YesNo::Yes),
+            is_no_wrap_root: self.is_no_wrap_root,
+            is_xmllint_attributes: is_xmllint && !self.uses_hex_entities,
+        }
+    }
+
+    /// The settings of a run, without the documents it was given, for the `--log-file` header.
+    fn settings(&self, mode: Mode) -> String {
+        let settings = FormatArgs {
+            xml_document_paths: vec![],
+            log_file: None,
+            ..self.clone()
+        };
+        format!("{:?} {:?}", mode, settings)
+    }
+
+    /// The options given that change more of a document than its indentation, which
+    /// `--reindent-only` cannot apply.
+    fn rewriting_options(&self) -> Vec<&'static str> {
+        [
+            (self.is_sort_attributes, "--sort-attributes"),
+            (!self.strip_attributes.is_empty(), "--strip-attr"),
+            (!self.boolean_attributes.is_empty(), "--expand-boolean-attr"),
+            (!self.case_rules.is_empty(), "--normalize-attr-case"),
+            (self.round_numbers.is_some(), "--round-numbers"),
+            (self.sort_children.is_some(), "--sort-children"),
+            (self.is_fix_default_namespace, "--fix-default-namespace"),
+            (
+                self.is_fix_encoding_declaration,
+                "--fix-encoding-declaration",
+            ),
+            (self.is_omit_utf8_encoding, "--omit-utf8-encoding"),
+            (
+                self.invalid_chars == Some(InvalidCharPolicy::Strip),
+                "--invalid-chars strip",
+            ),
+            (self.end_pad.is_some(), "--end-pad"),
+            (
+                self.max_line_length.is_some() || self.print_width.is_some(),
+                "--max-line-length",
+            ),
+            (self.uses_hex_entities, "--hex-entities"),
+            (self.compat.is_some(), "--compat"),
+            (self.is_no_text_indent, "--no-text-indent"),
+            (self.prolog_blank_line.is_some(), "--prolog-blank-line"),
+            (self.is_wrap_comments, "--wrap-comments"),
+            (self.is_no_wrap_root, "--no-wrap-root"),
+        ]
+        .into_iter()
+        .filter_map(|(is_given, name)| is_given.then_some(name))
+        .collect()
+    }
+
+    fn extensions(&self) -> Vec<String> {
+        match self.extensions.as_deref() {
+            Some(extensions) => extensions
+                .split(',')
+                .map(|ext| ext.trim().trim_start_matches('.').to_string())
+                .filter(|ext| !ext.is_empty())
+                .collect(),
+            None => vec!["xml".to_string()],
+        }
+    }
+
+    fn sort_exemptions(&self) -> SortExemptions {
+        SortExemptions::new(
+            self.no_sort_attributes_in
+                .iter()
+                .flat_map(|names| names.split(','))
+                .map(str::trim)
+                .filter(|name| !name.is_empty()),
+        )
+    }
+
+    fn display_path(&self, path: &Path) -> PathBuf {
+        files::display_path(path, self.path_style.unwrap_or_default())
+    }
+
+    fn comparison(&self) -> Comparison {
+        Comparison {
+            is_newline_insensitive: self.is_newline_insensitive,
+            is_trailing_space_insensitive: self.is_trailing_space_insensitive,
+        }
+    }
+}
+
+// `minify` and `tree` share the document pipeline with `format`; the formatting options they
+// do not offer are left at their defaults.
+impl From<CheckArgs> for FormatArgs {
+    fn from(args: CheckArgs) -> Self {
+        let CheckArgs {
+            help: _,
+            xml_document_paths,
+            extensions,
+            is_detect_xml,
+            is_verbose,
+            is_progress,
+            is_fail_fast,
+            is_stdin,
+            input_fd,
+            stdin_filepath,
+            is_newline_insensitive,
+            is_trailing_space_insensitive,
+            diff_tool,
+            is_sort_attributes,
+            no_sort_attributes_in,
+            strip_attributes,
+            boolean_attributes,
+            case_rules,
+            round_numbers,
+            is_check_sorted_attributes,
+            check_sorted_children,
+            xmlns_sort,
+            sort_children,
+            sort_children_by,
+            undeclared_prefix,
+            invalid_chars,
+            check_default_namespace,
+            is_fix_default_namespace,
+            is_fix_encoding_declaration,
+            is_omit_utf8_encoding,
+            indent,
+            indent_size,
+            indent_style,
+            is_reindent_only,
+            end_pad,
+            max_line_length,
+            print_width,
+            uses_hex_entities,
+            compat,
+            is_no_text_indent,
+            prolog_blank_line,
+            is_wrap_comments,
+            comment_width,
+            is_no_wrap_root,
+            path_style,
+            is_allow_empty,
+            is_fragment,
+            timings,
+            log_file,
+            summary_json,
+        } = args;
+        FormatArgs {
+            xml_document_paths,
+            extensions,
+            is_detect_xml,
+            is_verbose,
+            is_progress,
+            is_fail_fast,
+            is_stdin,
+            input_fd,
+            stdin_filepath,
+            is_newline_insensitive,
+            is_trailing_space_insensitive,
+            diff_tool,
+            is_sort_attributes,
+            no_sort_attributes_in,
+            strip_attributes,
+            boolean_attributes,
+            case_rules,
+            round_numbers,
+            is_check_sorted_attributes,
+            check_sorted_children,
+            xmlns_sort,
+            sort_children,
+            sort_children_by,
+            undeclared_prefix,
+            invalid_chars,
+            check_default_namespace,
+            is_fix_default_namespace,
+            is_fix_encoding_declaration,
+            is_omit_utf8_encoding,
+            indent,
+            indent_size,
+            indent_style,
+            is_reindent_only,
+            end_pad,
+            max_line_length,
+            print_width,
+            uses_hex_entities,
+            compat,
+            is_no_text_indent,
+            prolog_blank_line,
+            is_wrap_comments,
+            comment_width,
+            is_no_wrap_root,
+            path_style,
+            is_allow_empty,
+            is_fragment,
+            timings,
+            log_file,
+            summary_json,
+            ..FormatArgs::default()
+        }
+    }
+}
+
+impl From<MinifyArgs> for FormatArgs {
+    fn from(args: MinifyArgs) -> Self {
+        FormatArgs {
+            xml_document_paths: args.xml_document_paths,
+            extensions: args.extensions,
+            is_detect_xml: args.is_detect_xml,
+            is_verbose: args.is_verbose,
+            output_path: args.output_path,
+            is_replace: args.is_replace,
+            ..FormatArgs::default()
+        }
+    }
+}
+
+impl From<TreeArgs> for FormatArgs {
+    fn from(args: TreeArgs) -> Self {
+        FormatArgs {
+            xml_document_paths: args.xml_document_paths,
+            extensions: args.extensions,
+            is_detect_xml: args.is_detect_xml,
+            is_verbose: args.is_verbose,
+            ..FormatArgs::default()
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let mut argv = env::args().collect::<Vec<_>>();
+    let program = argv.remove(0);
+
+    // Anything that does not start with a command name is a `format` invocation, so that
+    // `xml-pretty file.xml` keeps working. A lone `-h` shows the list of commands instead.
+    let is_command = argv.first().is_some_and(|arg| is_command_name(arg));
+    let is_top_level_help = matches!(&argv[..], [arg] if arg == "-h" || arg == "--help");
+    if !is_command && !is_top_level_help {
+        match outline_alias(&argv) {
+            Some(tree) => argv = tree,
+            None => argv.insert(0, "format".to_string()),
+        }
+    }
+
+    let cli = parse_cli_or_exit(&program, &argv);
+
+    match cli.command {
+        Some(Command::Format(args)) => {
+            let mode = if args.is_dump_tree {
+                Mode::Dump
+            } else if args.lint_mode {
+                Mode::Check
+            } else {
+                Mode::Format
+            };
+            run(&args, mode)
+        }
+        Some(Command::Check(args)) => run(&args.into(), Mode::Check),
+        Some(Command::Minify(args)) => run(&args.into(), Mode::Minify),
+        Some(Command::Tree(args)) => {
+            let mode = Mode::Tree {
+                max_depth: args.depth,
+                include_attrs: args.attrs,
+            };
+            run(&args.into(), mode)
+        }
+        Some(Command::Diff(args)) => exit(run_diff(args)),
+        None => run(&FormatArgs::default(), Mode::Format),
+    }
+}
+
+fn is_command_name(arg: &str) -> bool {
+    matches!(arg, "format" | "check" | "minify" | "diff" | "tree")
+}
+
+/// `--outline`, `--outline-depth N` and `--outline-attrs` came before the `tree` command and
+/// its `--depth N` and `--attrs`. They are still accepted, though no longer listed, by turning
+/// a bare invocation that uses them into a `tree` one.
+fn outline_alias(argv: &[String]) -> Option<Vec<String>> {
+    let is_outline = argv
+        .iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--outline" || arg.starts_with("--outline-"));
+    if !is_outline {
+        return None;
+    }
+
+    let mut tree = vec!["tree".to_string()];
+    let mut is_options = true;
+    for arg in argv {
+        is_options &= arg != "--";
+        if !is_options {
+            tree.push(arg.clone());
+        } else if let Some(rest) = arg.strip_prefix("--outline-") {
+            tree.push(format!("--{}", rest));
+        } else if arg != "--outline" {
+            tree.push(arg.clone());
+        }
+    }
+    Some(tree)
+}
+
+/// Parses `argv` (without the program name), printing usage for the selected command and
+/// exiting if help was requested, or printing the error and exiting with status 2.
+fn parse_cli_or_exit(program: &str, argv: &[String]) -> Cli {
+    let cli = Cli::parse_args_default(argv).unwrap_or_else(|e| {
+        eprintln!("{}: {}", program, e);
+        exit(USAGE_ERROR);
+    });
+
+    if !cli.help_requested() {
+        return cli;
+    }
+
+    match cli.command() {
+        Some(command) => {
+            eprintln!(
+                "Usage: {} {} [OPTIONS]",
+                program,
+                command.command_name().unwrap_or_default()
+            );
+            eprintln!();
+            eprintln!("{}", command.self_usage());
+        }
+        None => {
+            eprintln!("Usage: {} [COMMAND] [OPTIONS]", program);
+            eprintln!();
+            eprintln!("{}", Cli::usage());
+            eprintln!();
+            eprintln!("Available commands:");
+            eprintln!("{}", Cli::command_list().unwrap_or_default());
+            eprintln!();
+            eprintln!("Without a command, the format command is run.");
+            eprintln!(
+                "Run `{} <COMMAND> -h` for the options of a command.",
+                program
+            );
+        }
+    }
+
+    exit(0);
+}
+
+/// The exit status for options that conflict or are missing, as for ones that don't parse.
+const USAGE_ERROR: i32 = 2;
+
+/// Runs `mode` over every document selected by `args`.
+fn run(args: &FormatArgs, mode: Mode) -> anyhow::Result<()> {
+    let formatting = args.formatting();
+    let comparison = args.comparison();
+
+    if args.indent_style == Some(IndentStyle::Tab) && !args.is_reindent_only {
+        // xmlem only indents with spaces, and rewriting its output could change CDATA and
+        // comments that span lines.
+        eprintln!(
+            "ERROR: --indent-style tab needs --reindent-only; xmlem only indents with spaces."
+        );
+        exit(USAGE_ERROR);
+    }
+
+    if args.is_reindent_only {
+        let rewriting = args.rewriting_options();
+        if !rewriting.is_empty() {
+            eprintln!(
+                "ERROR: --reindent-only leaves everything but indentation as written, so it cannot be combined with {}.",
+                rewriting.join(", ")
+            );
+            exit(USAGE_ERROR);
+        }
+    }
+
+    if args.is_fix_default_namespace && args.check_default_namespace.is_none() {
+        eprintln!(
+            "ERROR: --fix-default-namespace needs the namespace from --check-default-namespace."
+        );
+        exit(USAGE_ERROR);
+    }
+
+    if args.end_pad.is_some_and(|end_pad| end_pad > MAX_PADDING) {
+        eprintln!("ERROR: --end-pad can be at most {}.", MAX_PADDING);
+        exit(USAGE_ERROR);
+    }
+
+    if cfg!(not(unix)) && (args.input_fd.is_some() || args.output_fd.is_some()) {
+        eprintln!("ERROR: --input-fd and --output-fd are only available on Unix.");
+        exit(USAGE_ERROR);
+    }
+
+    for (flag, fd) in [
+        ("--input-fd", args.input_fd),
+        ("--output-fd", args.output_fd),
+    ] {
+        if let Some(fd) = fd.filter(|fd| !is_open_fd(*fd)) {
+            eprintln!("ERROR: {} {} is not an open file descriptor.", flag, fd);
+            exit(USAGE_ERROR);
+        }
+    }
+
+    if args.input_fd.is_some() && !args.xml_document_paths.is_empty() {
+        eprintln!("ERROR: cannot read from --input-fd and from paths at once.");
+        exit(USAGE_ERROR);
+    }
+
+    if args.is_stdin && !args.xml_document_paths.is_empty() {
+        eprintln!("ERROR: cannot read from --stdin and from paths at once.");
+        eprintln!("Use -o to choose where the formatted document is written.");
+        exit(USAGE_ERROR);
+    }
+
+    if !args.is_stdin && !args.xml_document_paths.is_empty() && is_stdin_redirected() {
+        eprintln!(
+            "WARNING: stdin is ignored because paths were given; only the paths are formatted."
+        );
+        eprintln!("Use --stdin -o <path> to format stdin into a file.");
+    }
+
+    if args.output_fd.is_some() && (args.output_path.is_some() || args.is_replace) {
+        eprintln!("ERROR: --output-fd cannot be combined with -o or --replace.");
+        exit(USAGE_ERROR);
+    }
+
+    let inputs = if !args.xml_document_paths.is_empty() {
+        let expanded = files::expand(
+            &args.xml_document_paths,
+            &args.extensions(),
+            args.is_detect_xml,
+        );
+        for duplicate in &expanded.duplicates {
+            eprintln!(
+                "WARNING: '{}' is the same file as '{}', so it is only processed once.",
+                args.display_path(&duplicate.path).display(),
+                args.display_path(&duplicate.first).display()
+            );
+        }
+        expanded.inputs.into_iter().map(Some).collect::<Vec<_>>()
+    } else if args.input_fd.is_none() && !args.is_stdin && io::stdin().is_terminal() {
+        eprintln!("ERROR: No XML document provided.");
+        eprintln!("Run with -h for usage information.");
+        exit(USAGE_ERROR);
+    } else {
+        vec![None]
+    };
+
+    if args.is_replace && inputs.iter().any(Option::is_none) {
+        eprintln!("ERROR: cannot replace 'file' when provided stdin data.");
+        exit(USAGE_ERROR);
+    }
+
+    if args.output_path.is_some() && inputs.len() > 1 {
+        eprintln!("ERROR: cannot write multiple documents to one output file.");
+        eprintln!("Use --replace to format each document in place.");
+        exit(USAGE_ERROR);
+    }
+
+    if let Some(command) = args.diff_tool.as_deref() {
+        if !matches!(mode, Mode::Check) {
+            eprintln!("ERROR: --diff-tool only applies when linting, with check or --lint.");
+            exit(USAGE_ERROR);
+        }
+        match shell_words::split(command) {
+            Ok(words) if words.is_empty() => {
+                eprintln!("ERROR: --diff-tool needs a command to run.");
+                exit(USAGE_ERROR);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("ERROR: cannot split the --diff-tool command: {}.", e);
+                exit(USAGE_ERROR);
+            }
+        }
+    }
+
+    if args.is_fragment && args.is_dump_tree {
+        eprintln!(
+            "ERROR: --dump-tree shows whole documents, so it cannot be combined with --fragment."
+        );
+        exit(USAGE_ERROR);
+    }
+
+    if args.comment_width.is_some() && !args.is_wrap_comments {
+        eprintln!("ERROR: --comment-width only applies with --wrap-comments.");
+        exit(USAGE_ERROR);
+    }
+
+    if args.is_accept && args.snapshot_dir.is_none() {
+        eprintln!("ERROR: --accept needs --snapshot-dir to say where the snapshots are.");
+        exit(USAGE_ERROR);
+    }
+
+    if args.snapshot_dir.is_some() {
+        if args.is_replace || args.output_path.is_some() || args.output_fd.is_some() {
+            eprintln!(
+                "ERROR: --snapshot-dir cannot be combined with -o, --replace or --output-fd."
+            );
+            exit(USAGE_ERROR);
+        }
+        if matches!(mode, Mode::Check) {
+            eprintln!("ERROR: --snapshot-dir compares documents itself; leave out --lint.");
+            exit(USAGE_ERROR);
+        }
+        if inputs.iter().any(Option::is_none) && args.stdin_filepath.is_none() {
+            eprintln!("ERROR: --snapshot-dir needs a file name; use --stdin-filepath for stdin.");
+            exit(USAGE_ERROR);
+        }
+        let mut names = inputs
+            .iter()
+            .filter_map(|input| match input {
+                Some(Ok(input)) => input.path.file_name(),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        if let Some(pair) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            eprintln!(
+                "ERROR: more than one document is named '{}', so they would share a snapshot.",
+                pair[0].to_string_lossy()
+            );
+            exit(USAGE_ERROR);
+        }
+    }
+
+    let audit_log = match args.log_file.as_deref() {
+        Some(path) => match AuditLog::open(path, &args.settings(mode)) {
+            Ok(log) => Some(log),
+            Err(e) => {
+                eprintln!("ERROR: cannot open log file '{}': {}", path.display(), e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    let mut reports = Reports {
+        log: audit_log,
+        summary: args.summary_json.as_ref().map(|_| Summary::default()),
+    };
+
+    let mut timings = Timings::start();
+    let mut failures = 0;
+    let mut skipped = 0;
+    let mut last_error = None;
+
+    let total = inputs.len();
+    let progress = Progress::new(mode.verb(), total, args.is_progress);
+
+    let mut inputs = inputs.into_iter().enumerate();
+    for (i, input) in inputs.by_ref() {
+        progress.clear();
+
+        let result = match input {
+            // A problem found while expanding the paths, e.g. a directory we may not read.
+            Some(Err(e)) => {
+                reports.record(&audit::Entry {
+                    path: None,
+                    action: "error",
+                    bytes_before: None,
+                    written: None,
+                    error: Some(format!("{:#}", e)),
+                    categories: &[],
+                    location: None,
+                    timings: None,
+                });
+                Err(e)
+            }
+            Some(Ok(input)) => {
+                if args.is_verbose {
+                    let path = args.display_path(&input.path);
+                    if input.is_sniffed {
+                        eprintln!("{} (detected as XML by content)", path.display());
+                    } else {
+                        eprintln!("{}", path.display());
+                    }
+                }
+                run_timed(
+                    args,
+                    mode,
+                    &formatting,
+                    &comparison,
+                    Some(&input.path),
+                    total,
+                    &mut timings,
+                    &mut reports,
+                )
+            }
+            None => {
+                if args.is_verbose {
+                    eprintln!("<stdin>");
+                }
+                run_timed(
+                    args,
+                    mode,
+                    &formatting,
+                    &comparison,
+                    None,
+                    total,
+                    &mut timings,
+                    &mut reports,
+                )
+            }
+        };
+
+        if let Err(e) = result {
+            failures += 1;
+            if total > 1 {
+                eprintln!("Error: {:?}", e);
+            } else {
+                last_error = Some(e);
+            }
+
+            if args.is_fail_fast {
+                skipped = total - i - 1;
+                break;
+            }
+        }
+
+        progress.update(i + 1);
+    }
+
+    progress.clear();
+
+    for (_, input) in inputs {
+        if let Some(Ok(input)) = input {
+            let path = args.display_path(&input.path).display().to_string();
+            reports.record(&audit::Entry {
+                path: Some(&path),
+                action: "skipped",
+                bytes_before: std::fs::metadata(&input.path).ok().map(|m| m.len()),
+                written: None,
+                error: None,
+                categories: &[],
+                location: None,
+                timings: None,
+            });
+        }
+    }
+
+    if let (Some(path), Some(summary)) = (args.summary_json.as_deref(), reports.summary.as_ref()) {
+        let json = summary.to_json(&timings);
+        let written = if path == Path::new("-") {
+            io::stderr().write_all(json.as_bytes())
+        } else {
+            write(path, json)
+        };
+        if let Err(e) = written {
+            eprintln!(
+                "WARNING: cannot write the summary to '{}': {}",
+                path.display(),
+                e
+            );
+        }
+    }
+
+    if args.timings {
+        eprint!("{}", timings);
+    }
+
+    match last_error {
+        Some(e) => Err(e),
+        None if skipped > 0 => Err(anyhow::anyhow!(
+            "stopped at the first failure; {} of {} documents were skipped",
+            skipped,
+            total
+        )),
+        None if failures > 0 => Err(anyhow::anyhow!(
+            "{} of {} documents failed",
+            failures,
+            total
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Runs one document, adding its timings to `timings` and printing them too in verbose batches.
+/// With `--log-file` or `--summary-json`, what happened to it is recorded in `reports`.
+#[allow(clippy::too_many_arguments)]
+fn run_timed(
+    args: &FormatArgs,
+    mode: Mode,
+    formatting: &Formatting,
+    comparison: &Comparison,
+    input_path: Option<&Path>,
+    total: usize,
+    timings: &mut Timings,
+    reports: &mut Reports,
+) -> anyhow::Result<()> {
+    let is_recording = reports.log.is_some() || reports.summary.is_some();
+    // Where the output goes if it is written to a file, and what that file held before.
+    let target = match (is_recording, args.is_replace) {
+        (false, _) => None,
+        (true, true) => input_path.map(Path::to_path_buf),
+        (true, false) => args.output_path.clone(),
+    };
+    let crc_before = target
+        .as_deref()
+        .and_then(|target| std::fs::read(target).ok())
+        .map(|bytes| audit::crc32(&bytes));
+    let bytes_before = input_path
+        .filter(|_| is_recording)
+        .and_then(|path| std::fs::metadata(path).ok())
+        .map(|metadata| metadata.len());
+
+    let mut document_timings = Timings::start();
+    let result = run_document(
+        args,
+        mode,
+        formatting,
+        comparison,
+        input_path,
+        &mut document_timings,
+    );
+    timings.add(&document_timings);
+    // Taken once, so the log and the summary agree on the total.
+    let timings_json = document_timings.to_json();
+
+    if args.timings && args.is_verbose && total > 1 {
+        eprint!("{}", document_timings);
+    }
+
+    if is_recording {
+        let written = target
+            .as_deref()
+            .filter(|_| result.is_ok())
+            .and_then(|target| std::fs::read(target).ok())
+            .map(|bytes| (bytes.len() as u64, audit::crc32(&bytes)));
+        let action = match (&result, written) {
+            (Err(e), _) if e.downcast_ref::<lint::NotFormatted>().is_some() => "unformatted",
+            (Err(_), _) => "error",
+            (Ok(_), Some((_, crc))) if Some(crc) == crc_before => "unchanged",
+            (Ok(_), Some(_)) => "rewritten",
+            (Ok(_), None) if matches!(mode, Mode::Check) => "checked",
+            (Ok(_), None) => "printed",
+        };
+        let path = input_path
+            .or(args.stdin_filepath.as_deref())
+            .map(|path| args.display_path(path).display().to_string());
+        reports.record(&audit::Entry {
+            path: path.as_deref(),
+            action,
+            bytes_before,
+            written,
+            error: result.as_ref().err().map(|e| format!("{:#}", e)),
+            categories: result.as_ref().err().map_or(&[], error_categories),
+            location: result.as_ref().err().and_then(error_location),
+            timings: Some(timings_json),
+        });
+    }
+
+    result
+}
+
+/// Where what happened to each document is recorded: the `--log-file` as it happens, and the
+/// `--summary-json` report at the end.
+struct Reports {
+    log: Option<AuditLog>,
+    summary: Option<Summary>,
+}
+
+impl Reports {
+    /// Adds `entry` to the reports being kept. A log that can't be written to doesn't stop the
+    /// run, but is reported.
+    fn record(&mut self, entry: &audit::Entry) {
+        if let Some(log) = self.log.as_mut() {
+            if let Err(e) = log.record(entry) {
+                eprintln!("WARNING: cannot write to the log file: {}", e);
+            }
+        }
+        if let Some(summary) = self.summary.as_mut() {
+            summary.add(entry);
+        }
+    }
+}
+
+/// The line and column `e` was found at, for the errors that know it.
+fn error_location(e: &anyhow::Error) -> Option<(usize, usize)> {
+    e.chain().find_map(|cause| {
+        if let Some(trailing) = cause.downcast_ref::<markup::TrailingContent>() {
+            Some((trailing.line, trailing.column))
+        } else if let Some(invalid) = cause.downcast_ref::<characters::InvalidChar>() {
+            Some((invalid.0.line, invalid.0.column))
+        } else {
+            cause
+                .downcast_ref::<lint::NotFormatted>()
+                .and_then(|not_formatted| not_formatted.location)
+        }
+    })
+}
+
+/// The kinds of difference of a document `check` found not to be formatted.
+fn error_categories(e: &anyhow::Error) -> &[&'static str] {
+    e.chain()
+        .find_map(|cause| cause.downcast_ref::<lint::NotFormatted>())
+        .map_or(&[], |not_formatted| &not_formatted.categories)
+}
+
+/// Whether stdin is a pipe or a file, which probably means the user meant to format it. A
+/// terminal or `/dev/null` doesn't count.
+#[cfg(unix)]
+fn is_stdin_redirected() -> bool {
+    use std::os::{fd::AsFd, unix::fs::FileTypeExt};
+
+    io::stdin()
+        .as_fd()
+        .try_clone_to_owned()
+        .map(File::from)
+        .and_then(|stdin| stdin.metadata())
+        .is_ok_and(|metadata| metadata.file_type().is_fifo() || metadata.is_file())
+}
+
+#[cfg(not(unix))]
+fn is_stdin_redirected() -> bool {
+    false
+}
+
+/// Runs `mode` on a single document from `input_path`, or from stdin (or `--input-fd`) if
+/// `None`.
+fn run_document(
+    args: &FormatArgs,
+    mode: Mode,
+    formatting: &Formatting,
+    comparison: &Comparison,
+    input_path: Option<&Path>,
+    timings: &mut Timings,
+) -> anyhow::Result<()> {
+    let source = Source::new(args, input_path);
+    let sink = Sink::new(args, input_path);
+
+    if let (false, Some(input_path), Sink::Path(output_path)) = (args.is_replace, input_path, &sink)
+    {
+        if is_same_file(input_path, output_path) {
+            return Err(anyhow::anyhow!(
+                "'{}' is the input document; use --replace to format it in place",
+                output_path.display()
+            ));
+        }
+    }
+
+    let invalid_chars = args.invalid_chars.unwrap_or_default();
+    let shown_path = input_path.or(args.stdin_filepath.as_deref());
+    let input_name = match (shown_path, source) {
+        (Some(path), _) => format!("at path: `{}`", args.display_path(path).display()),
+        (None, Source::Fd(fd)) => format!("from file descriptor {}", fd),
+        (None, _) => "from stdin".to_string(),
+    };
+
+    let loaded = source
+        .load(invalid_chars, args.is_fragment, timings)
+        .with_context(|| match shown_path {
+            Some(path) => format!("Failed to prettify '{}'", args.display_path(path).display()),
+            None => format!("Failed to prettify {}", input_name),
+        });
+
+    let Loaded {
+        mut doc,
+        original,
+        is_gzip: is_gzip_input,
+        encoding_mismatch,
+        has_root,
+        characters,
+    } = match loaded {
+        Err(e) if args.is_allow_empty && e.downcast_ref::<EmptyDocument>().is_some() => {
+            // An empty document formats to nothing. Only a separate output file needs writing.
+            if let (false, Sink::Path(path)) = (args.is_replace, &sink) {
+                if !matches!(mode, Mode::Check | Mode::Tree { .. } | Mode::Dump) {
+                    write(path, "")
+                        .with_context(|| format!("Failed to write to '{}'", path.display()))?;
+                }
+            }
+            return Ok(());
+        }
+        loaded => loaded?,
+    };
+
+    let is_check = matches!(mode, Mode::Check);
+
+    if let Some(mismatch) = encoding_mismatch {
+        if is_check {
+            return Err(anyhow::anyhow!(
+                "xml-pretty --lint failed for document {}: {}",
+                input_name,
+                mismatch
+            ));
+        }
+        eprintln!("WARNING: document {}: {}", input_name, mismatch);
+    }
+
+    for warning in characters.warnings(invalid_chars) {
+        eprintln!("WARNING: document {}: {}", input_name, warning);
+    }
+
+    if args.is_fix_encoding_declaration {
+        encoding::fix_declaration(&mut doc);
+    }
+
+    if args.compat == Some(Compat::Xmllint) && doc.declaration().is_none() {
+        doc.set_declaration(Some(Declaration {
+            version: Some("1.0".to_string()),
+            encoding: None,
+            standalone: None,
+        }));
+    }
+
+    if args.is_omit_utf8_encoding {
+        omit_utf8_encoding(&mut doc);
+    }
+
+    let undeclared =
+        namespaces::check_prefixes(&mut doc, args.undeclared_prefix.unwrap_or_default())
+            .map_err(|e| anyhow::anyhow!("document {}: {}", input_name, e))?;
+    for undeclared in undeclared {
+        eprintln!("WARNING: document {}: {}", input_name, undeclared);
+    }
+
+    if is_check {
+        let sort_children_by = args.sort_children_by.clone().unwrap_or(SortKey::TagName);
+        let locator = outline::Locator::new(&doc, &original);
+        let mut findings = vec![];
+        if args.is_check_sorted_attributes {
+            findings.extend(
+                attributes::check_sorted(
+                    &doc,
+                    args.xmlns_sort.unwrap_or_default(),
+                    &args.sort_exemptions(),
+                    &locator,
+                )
+                .iter()
+                .map(ToString::to_string),
+            );
+        }
+        if let Some(parents) = args.check_sorted_children.as_ref() {
+            findings.extend(
+                children::check_sorted(&doc, parents, &sort_children_by, &locator)
+                    .iter()
+                    .map(ToString::to_string),
+            );
+        }
+        if let Some(namespace) = args.check_default_namespace.as_deref() {
+            findings.extend(
+                namespaces::check_default_namespace(&doc, namespace, &locator)
+                    .iter()
+                    .map(ToString::to_string),
+            );
+        }
+        findings.extend(
+            attributes::check_case(&doc, &args.case_rules, &locator)
+                .iter()
+                .map(ToString::to_string),
+        );
+        if let Some(rounding) = args.round_numbers.as_ref() {
+            findings.extend(
+                numbers::check_rounded(&doc, rounding, &locator)
+                    .iter()
+                    .map(ToString::to_string),
+            );
+        }
+        if !findings.is_empty() {
+            return Err(anyhow::anyhow!(
+                "xml-pretty --lint failed for document {}:\n  {}",
+                input_name,
+                findings.join("\n  ")
+            ));
+        }
+    }
+
+    if let (true, Some(namespace)) = (
+        args.is_fix_default_namespace,
+        args.check_default_namespace.as_deref(),
+    ) {
+        namespaces::fix_default_namespace(&mut doc, namespace);
+    }
+
+    if !args.strip_attributes.is_empty() {
+        attributes::strip_attributes(&mut doc, &args.strip_attributes);
+    }
+
+    if !args.case_rules.is_empty() {
+        attributes::normalize_case(&mut doc, &args.case_rules);
+    }
+
+    if let Some(rounding) = args.round_numbers.as_ref() {
+        numbers::round_numbers(&mut doc, rounding);
+    }
+
+    if !args.boolean_attributes.is_empty() {
+        attributes::expand_boolean_attributes(&mut doc, &args.boolean_attributes);
+    }
+
+    if let Some(parents) = args.sort_children.as_ref() {
+        let by = args.sort_children_by.clone().unwrap_or(SortKey::TagName);
+        for name in children::sort_children(&mut doc, parents, &by) {
+            eprintln!(
+                "WARNING: document {}: not sorting the children of <{}>, which contain text",
+                input_name, name
+            );
+        }
+    }
+
+    if args.is_sort_attributes {
+        attributes::sort_attributes(
+            &mut doc,
+            args.xmlns_sort.unwrap_or_default(),
+            &args.sort_exemptions(),
+        );
+    }
+
+    if args.is_wrap_comments && matches!(mode, Mode::Format | Mode::Check) {
+        comments::wrap_comments(
+            &mut doc,
+            formatting.indent,
+            formatting.max_line_length,
+            args.comment_width,
+        );
+    }
+
+    let formatted = match mode {
+        Mode::Tree {
+            max_depth,
+            include_attrs,
+        } => {
+            if !has_root {
+                return Ok(());
+            }
+            let outline = outline::outline(&doc, max_depth, include_attrs);
+            return print_stdout(&outline);
+        }
+        Mode::Dump => {
+            let dump = dump::dump(&doc);
+            let placeholder = format!("Element <{}>\n", markup::PLACEHOLDER_ROOT);
+            return match (has_root, dump.strip_suffix(&placeholder)) {
+                (false, Some(prolog)) => print_stdout(prolog),
+                _ => print_stdout(&dump),
+            };
+        }
+        Mode::Minify => timings.measure(|t| &mut t.serialize, || doc.to_string()),
+        Mode::Format | Mode::Check if args.is_reindent_only => {
+            // The document was only parsed to make sure it is well-formed; its text is kept.
+            let unit = match args.indent_style {
+                Some(IndentStyle::Tab) => "\t".to_string(),
+                _ => " ".repeat(formatting.indent),
+            };
+            let (reindented, irregular) = timings.measure(
+                |t| &mut t.serialize,
+                || reindent::reindent(&original, &unit),
+            );
+            if let Some(irregular) = irregular {
+                eprintln!("WARNING: document {}: {}", input_name, irregular);
+            }
+            reindented
+        }
+        Mode::Format | Mode::Check => timings
+            .measure(|t| &mut t.serialize, || prettify(doc, formatting))
+            .map_err(|e| anyhow::anyhow!("document {}: {}", input_name, e))?,
+    };
+    let formatted = if args.is_reindent_only {
+        formatted
+    } else if args.is_fragment {
+        without_fragment_root(formatted, formatting.indent)
+    } else if has_root {
+        formatted
+    } else {
+        without_placeholder_root(formatted)
+    };
+
+    if is_check {
+        let is_unchanged = timings.measure(
+            |t| &mut t.compare,
+            || comparison.is_equal(&formatted, &original),
+        );
+        if is_unchanged {
+            return Ok(());
+        } else {
+            let categories =
+                timings.measure(|t| &mut t.compare, || lint::classify(&formatted, &original));
+            if let Some(command) = args.diff_tool.as_deref() {
+                if let Some(warning) = diff_tool::run(command, &original, &formatted) {
+                    eprintln!("WARNING: document {}: {}", input_name, warning);
+                }
+            }
+            let location = {
+                let (original, formatted) = (
+                    comparison.normalize(&original),
+                    comparison.normalize(&formatted),
+                );
+                lint::first_difference(&original, &formatted)
+                    .map(|difference| (difference.line, difference.column + 1))
+            };
+            return Err(lint::NotFormatted {
+                input_name,
+                categories,
+                location,
+            }
+            .into());
+        }
+    }
+
+    if let (Some(dir), Some(path)) = (args.snapshot_dir.as_deref(), shown_path) {
+        return match path.file_name() {
+            Some(name) => check_snapshot(&dir.join(name), &formatted, args.is_accept)
+                .map_err(|e| anyhow::anyhow!("document {}: {}", input_name, e)),
+            None => Err(anyhow::anyhow!(
+                "document {} has no file name to find its snapshot by",
+                input_name
+            )),
+        };
+    }
+
+    // Streams get the same bytes as a file would, plus a final newline if (as with minify) the
+    // output has none, so redirecting stdout to a file gives one that passes the lint.
+    let final_newline = if formatted.ends_with('\n') { "" } else { "\n" };
+
+    timings.measure(
+        |t| &mut t.write,
+        || -> anyhow::Result<()> {
+            match &sink {
+                Sink::Path(path) => {
+                    let is_gzip_output =
+                        gzip::has_gz_extension(path) || (args.is_replace && is_gzip_input);
+                    write_file(path, &formatted, is_gzip_output)
+                        .with_context(|| format!("Failed to write to '{}'", path.display()))?;
+                }
+                Sink::Fd(fd) => {
+                    let mut file = fd_file(*fd);
+                    file.write_all(formatted.as_bytes())
+                        .and_then(|_| file.write_all(final_newline.as_bytes()))
+                        .with_context(|| format!("Failed to write to file descriptor {}", fd))?;
+                }
+                Sink::Stdout => {
+                    if let Some(template) = args.stdout_header.as_deref() {
+                        let path = shown_path.map_or("<stdin>".to_string(), |path| {
+                            files::forward_slashes(&args.display_path(path))
+                        });
+                        print_stdout(&stdout_header(template, &path))?;
+                    }
+                    print_stdout(&formatted)?;
+                    print_stdout(final_newline)?;
+                }
+            }
+            Ok(())
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Compares `formatted` with the snapshot at `path`, or with `accept` makes it the snapshot.
+fn check_snapshot(path: &Path, formatted: &str, accept: bool) -> anyhow::Result<()> {
+    if accept {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+        }
+        return write(path, formatted)
+            .with_context(|| format!("Failed to write snapshot '{}'", path.display()));
+    }
+
+    let expected = match std::fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            anyhow::bail!(
+                "snapshot '{}' does not exist; use --accept to create it",
+                path.display()
+            )
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read snapshot '{}'", path.display()))
+        }
+    };
+
+    match lint::first_difference(formatted, &expected) {
+        None => Ok(()),
+        Some(difference) => anyhow::bail!(
+            "differs from snapshot '{}' at line {}, column {}:\n  - {}\n  + {}",
+            path.display(),
+            difference.line,
+            difference.column + 1,
+            difference.excerpt(difference.expected),
+            difference.excerpt(difference.actual)
+        ),
+    }
+}
+
+/// Reads and parses the document at `path`, transparently decompressing gzip files.
+fn load_file(
+    path: &Path,
+    invalid_chars: InvalidCharPolicy,
+    is_fragment: bool,
+    timings: &mut Timings,
+) -> anyhow::Result<Loaded> {
+    let bytes = timings
+        .measure(|t| &mut t.read, || std::fs::read(path))
+        .with_context(|| format!("Failed to read file '{}'", path.display()))?;
+
+    let is_gzip = gzip::is_gzip(&bytes);
+    let bytes = if is_gzip {
+        timings
+            .measure(|t| &mut t.read, || gzip::decompress(&bytes))
+            .with_context(|| format!("Failed to decompress '{}'", path.display()))?
+    } else {
+        bytes
+    };
+
+    let loaded = parse_bytes(bytes, invalid_chars, is_fragment, timings)
+        .with_context(|| format!("Failed to read file '{}'", path.display()))?;
+    Ok(Loaded { is_gzip, ..loaded })
+}
+
+fn load_reader(
+    mut reader: impl Read,
+    invalid_chars: InvalidCharPolicy,
+    is_fragment: bool,
+    timings: &mut Timings,
+) -> anyhow::Result<Loaded> {
+    let mut buffer = Vec::new();
+    timings
+        .measure(|t| &mut t.read, || reader.read_to_end(&mut buffer))
+        .context("Failed to read input")?;
+
+    // Written out uncompressed, unless to a `-o` path ending in `.gz`.
+    let is_gzip = gzip::is_gzip(&buffer);
+    let buffer = if is_gzip {
+        timings
+            .measure(|t| &mut t.read, || gzip::decompress(&buffer))
+            .context("Failed to decompress input")?
+    } else {
+        buffer
+    };
+
+    let loaded = parse_bytes(buffer, invalid_chars, is_fragment, timings)?;
+    Ok(Loaded { is_gzip, ..loaded })
+}
+
+/// Where a document is read from.
+#[derive(Clone, Copy)]
+enum Source<'a> {
+    Path(&'a Path),
+    Stdin,
+    /// An open descriptor from `--input-fd`.
+    Fd(i32),
+}
+
+impl<'a> Source<'a> {
+    /// The document at `input_path`, or when `None` the one on stdin or `--input-fd`.
+    fn new(args: &FormatArgs, input_path: Option<&'a Path>) -> Self {
+        match (input_path, args.input_fd) {
+            (Some(path), _) => Source::Path(path),
+            (None, Some(fd)) => Source::Fd(fd),
+            (None, None) => Source::Stdin,
+        }
+    }
+
+    fn load(
+        self,
+        invalid_chars: InvalidCharPolicy,
+        is_fragment: bool,
+        timings: &mut Timings,
+    ) -> anyhow::Result<Loaded> {
+        match self {
+            Source::Path(path) => load_file(path, invalid_chars, is_fragment, timings),
+            Source::Stdin => load_reader(io::stdin().lock(), invalid_chars, is_fragment, timings),
+            Source::Fd(fd) => load_reader(&mut *fd_file(fd), invalid_chars, is_fragment, timings),
+        }
+    }
+}
+
+/// Where a formatted document is written.
+enum Sink {
+    /// The input document itself with `--replace`, or the `-o` file.
+    Path(PathBuf),
+    Stdout,
+    /// An open descriptor from `--output-fd`.
+    Fd(i32),
+}
+
+impl Sink {
+    fn new(args: &FormatArgs, input_path: Option<&Path>) -> Self {
+        match (
+            args.is_replace,
+            input_path,
+            &args.output_path,
+            args.output_fd,
+        ) {
+            (true, Some(path), _, _) => Sink::Path(path.to_path_buf()),
+            (false, _, Some(path), _) => Sink::Path(path.clone()),
+            (false, _, None, Some(fd)) => Sink::Fd(fd),
+            _ => Sink::Stdout,
+        }
+    }
+}
+
+/// Whether `fd` is a descriptor this process has open, so `--input-fd` and `--output-fd` can't
+/// name one that isn't.
+#[cfg(unix)]
+fn is_open_fd(fd: i32) -> bool {
+    // SAFETY: F_GETFD only reads the descriptor's flags, and fails for one that isn't open.
+    fd >= 0 && unsafe { libc::fcntl(fd, libc::F_GETFD) } != -1
+}
+
+#[cfg(not(unix))]
+fn is_open_fd(_fd: i32) -> bool {
+    false
+}
+
+/// An already open descriptor from `--input-fd` or `--output-fd`. It is left open, since the
+/// caller owns it and a batch writes every document to the same one.
+#[cfg(unix)]
+fn fd_file(fd: i32) -> ManuallyDrop<File> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: `fd` was checked to be open, and the caller passed it to xml-pretty to be used
+    // this way; the `ManuallyDrop` means it is never closed here.
+    ManuallyDrop::new(unsafe { File::from_raw_fd(fd) })
+}
+
+#[cfg(not(unix))]
+fn fd_file(_fd: i32) -> ManuallyDrop<File> {
+    unreachable!("file descriptors are only accepted on Unix")
+}
+
+/// Parses `bytes` as a document, or with `is_fragment` as a fragment wrapped in a
+/// [`markup::FRAGMENT_ROOT`] element.
+fn parse_bytes(
+    bytes: Vec<u8>,
+    invalid_chars: InvalidCharPolicy,
+    is_fragment: bool,
+    timings: &mut Timings,
+) -> anyhow::Result<Loaded> {
+    let encoding_mismatch = encoding::check(&bytes);
+
+    let original = match String::from_utf8(bytes) {
+        Ok(original) => original,
+        Err(e) => {
+            let e = anyhow::Error::new(e);
+            return Err(match encoding_mismatch {
+                Some(mismatch) => e.context(mismatch.to_string()),
+                None => e,
+            });
+        }
+    };
+
+    // The original is kept as it is for linting; stripped characters are only left out of the
+    // text that is parsed.
+    let (text, characters) = characters::check(&original, invalid_chars)?;
+    // A fragment always has its wrapper for a root.
+    let has_root = is_fragment || !markup::is_prolog_only(&text);
+    let doc = timings.measure(
+        |t| &mut t.parse,
+        || -> anyhow::Result<Document> {
+            if text.trim().is_empty() {
+                return Err(EmptyDocument.into());
+            }
+            if is_fragment {
+                return Ok(Document::from_str(&markup::wrap_fragment(&text))?);
+            }
+            if let Some(trailing) = markup::trailing_content(&text) {
+                return Err(trailing.into());
+            }
+            let doc = if has_root {
+                Document::from_str(&text)?
+            } else {
+                Document::from_str(&format!("{}<{}/>", text, markup::PLACEHOLDER_ROOT))?
+            };
+            let depth = outline::depth(&doc);
+            if depth > outline::MAX_DEPTH {
+                return Err(TooDeep { depth }.into());
+            }
+            Ok(doc)
+        },
+    )?;
+    Ok(Loaded {
+        doc,
+        original,
+        is_gzip: false,
+        encoding_mismatch,
+        has_root,
+        characters,
+    })
+}
+
+/// Drops `encoding="UTF-8"` from the declaration, since UTF-8 is what XML defaults to. Any
+/// other declared encoding is kept.
+fn omit_utf8_encoding(doc: &mut Document) {
+    if let Some(decl) = doc.declaration() {
+        if decl
+            .encoding
+            .as_deref()
+            .is_some_and(|encoding| encoding.eq_ignore_ascii_case("utf-8"))
+        {
+            let mut decl = decl.clone();
+            decl.encoding = None;
+            doc.set_declaration(Some(decl));
+        }
+    }
+}
+
+/// Whether `a` and `b` name the same existing file, following symlinks.
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn write_file(path: &Path, contents: &str, is_gzip: bool) -> io::Result<()> {
+    if is_gzip {
+        write(path, gzip::compress(contents.as_bytes())?)
+    } else {
+        write(path, contents)
+    }
+}
+
+/// Runs `xml-pretty diff`, returning the exit code: 0 if the documents are equivalent, 1 if
+/// they differ and 2 if either could not be read or parsed.
+fn run_diff(args: DiffArgs) -> i32 {
+    let [left_path, right_path] = &args.paths[..] else {
+        eprintln!("ERROR: diff takes exactly two XML documents.");
+        eprintln!("Run with -h for usage information.");
+        return 2;
+    };
+
+    let mut timings = Timings::start();
+    let load = |path: &Path, timings: &mut Timings| {
+        load_file(path, InvalidCharPolicy::default(), false, timings)
+            .map(|loaded| loaded.doc)
+            .with_context(|| format!("Failed to parse '{}'", path.display()))
+    };
+
+    let (left, right) = match (
+        load(left_path, &mut timings),
+        load(right_path, &mut timings),
+    ) {
+        (Ok(left), Ok(right)) => (left, right),
+        (Err(e), _) | (_, Err(e)) => {
+            eprintln!("Error: {:?}", e);
+            return 2;
+        }
+    };
+
+    match equivalence::compare(&left, &right) {
+        None => 0,
+        Some(difference) => match print_stdout(&format!("{}\n", difference)) {
+            Ok(()) => 1,
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                2
+            }
+        },
+    }
+}
+
+/// Renders `--stdout-header` as a comment line, substituting `{path}`. Any `--` is broken up
+/// so the comment stays well-formed.
+fn stdout_header(template: &str, path: &str) -> String {
+    let mut text = template.replace("{path}", path);
+    while text.contains("--") {
+        text = text.replace("--", "- -");
+    }
+    format!("<!-- {} -->\n", text.trim_end_matches('-'))
+}
+
+/// Writes `output` to stdout. A closed pipe (as in `xml-pretty big.xml | head`) is the normal
+/// way for a filter to be stopped, so it quietly ends the process with status 0.
+fn print_stdout(output: &str) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match stdout
+        .write_all(output.as_bytes())
+        .and_then(|_| stdout.flush())
+    {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => exit(0),
+        result => result.context("Failed to write to stdout"),
+    }
+}
+
+fn prettify(doc: Document, formatting: &Formatting) -> anyhow::Result<String> {
+    // Attributes and text of the deepest elements are indented one level further than they are.
+    let widest = outline::depth(&doc).saturating_mul(formatting.indent);
+    if widest > MAX_PADDING {
+        anyhow::bail!(
+            "indenting {} columns is more than the {} xmlem supports; use a smaller --indent",
+            widest,
+            MAX_PADDING
+        );
+    }
+
+    let mut formatted = doc.to_string_pretty_with_config(&formatting.config);
+
+    if formatting.is_no_wrap_root {
+        formatted = unwrap_root(formatted);
+    }
+
+    if formatting.is_xmllint_attributes {
+        formatted = markup::map_attribute_values(&formatted, xmllint_attribute_value);
+    }
+
+    Ok(if formatting.prolog_blank_line {
+        separate_prolog(formatted)
+    } else {
+        formatted
+    })
+}
+
+/// Joins the lines of the root element's start tag, so its attributes are separated by single
+/// spaces. xmlem escapes line breaks in attribute values, so any in the tag are its wrapping.
+fn unwrap_root(formatted: String) -> String {
+    let Some(start) = markup::root_element_offset(&formatted) else {
+        return formatted;
+    };
+    let Some(len) = markup::tag_len(&formatted[start..]) else {
+        return formatted;
+    };
+
+    let tag = &formatted[start..start + len];
+    if !tag.contains('\n') {
+        return formatted;
+    }
+    let unwrapped = tag
+        .split('\n')
+        .map(str::trim_start)
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "{}{}{}",
+        &formatted[..start],
+        unwrapped,
+        &formatted[start + len..]
+    )
+}
+
+/// An attribute value as xmllint writes it: xmlem escapes `'` even inside double quotes and
+/// writes the whitespace it escapes as four-digit hex references, where xmllint uses `'` and
+/// decimal references.
+fn xmllint_attribute_value(value: &str, quote: char) -> String {
+    let value = value
+        .replace("&#x0009;", "&#9;")
+        .replace("&#x000A;", "&#10;")
+        .replace("&#x000D;", "&#13;");
+    if quote == '"' {
+        value.replace("&apos;", "'")
+    } else {
+        value
+    }
+}
+
+/// Removes the placeholder root element given to a prolog-only document from the end of its
+/// output, keeping whatever line ending followed it.
+fn without_placeholder_root(formatted: String) -> String {
+    let content = formatted.trim_end();
+    let placeholder = format!("<{}/>", markup::PLACEHOLDER_ROOT);
+    match content.strip_suffix(&placeholder) {
+        Some(prolog) => format!("{}{}", prolog.trim_end(), &formatted[content.len()..]),
+        None => formatted,
+    }
+}
+
+/// Removes the [`markup::FRAGMENT_ROOT`] element a `--fragment` was wrapped in, taking its
+/// content out one level of `indent`.
+fn without_fragment_root(formatted: String, indent: usize) -> String {
+    let start_tag = format!("<{}", markup::FRAGMENT_ROOT);
+    let end_tag = format!("</{}>", markup::FRAGMENT_ROOT);
+    let Some(start) = formatted.find(&start_tag) else {
+        return formatted;
+    };
+    let Some(len) = markup::tag_len(&formatted[start..]) else {
+        return formatted;
+    };
+    let prolog = &formatted[..start];
+    let content_start = start + len;
+    let (content, rest) = match formatted[content_start..].rfind(&end_tag) {
+        Some(end) => (
+            &formatted[content_start..content_start + end],
+            &formatted[content_start + end + end_tag.len()..],
+        ),
+        // Written as an empty element.
+        None => ("", &formatted[content_start..]),
+    };
+
+    let unit = " ".repeat(indent);
+    let content = content
+        .trim_start_matches(['\r', '\n'])
+        .trim_end_matches([' ', '\t'])
+        .split('\n')
+        .map(|line| line.strip_prefix(unit.as_str()).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    match content.strip_suffix('\n') {
+        Some(content) => format!("{}{}{}", prolog, content, rest),
+        None if content.is_empty() => format!("{}{}", prolog.trim_end(), rest),
+        None => format!("{}{}{}", prolog, content, rest),
+    }
+}
+
+/// Puts exactly one blank line between the prolog and the root element. Documents without
+/// a prolog are returned unchanged.
+fn separate_prolog(formatted: String) -> String {
+    match markup::root_element_offset(&formatted) {
+        Some(offset) if offset > 0 => {
+            let (prolog, rest) = formatted.split_at(offset);
+            format!("{}\n\n{}", prolog.trim_end(), rest)
+        }
+        _ => formatted,
+    }
 }
@@ -0,0 +1,494 @@
+//! Just enough scanning of XML markup to find where the root element starts and ends, for
+//! the cases where xmlem's parsed tree no longer says where things were in the source.
+
+use std::fmt;
+
+/// Finds the byte offset of the root element's start tag by skipping over everything the
+/// prolog may contain: the declaration, processing instructions, comments, CDATA sections,
+/// the doctype (including its internal subset) and text.
+pub fn root_element_offset(s: &str) -> Option<usize> {
+    let mut offset = 0;
+
+    loop {
+        let rest = &s[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+
+        let len = if trimmed.starts_with("<?") {
+            trimmed.find("?>").map(|i| i + 2)
+        } else if trimmed.starts_with("<!--") {
+            trimmed.find("-->").map(|i| i + 3)
+        } else if trimmed.starts_with("<![CDATA[") {
+            trimmed.find("]]>").map(|i| i + 3)
+        } else if trimmed.starts_with("<!") {
+            doctype_len(trimmed)
+        } else if trimmed.starts_with('<') {
+            return Some(offset);
+        } else {
+            trimmed.find('<')
+        }?;
+
+        offset += len;
+    }
+}
+
+fn doctype_len(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+
+    for (i, ch) in s.char_indices() {
+        match (quote, ch) {
+            (Some(q), ch) if q == ch => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '[') => depth += 1,
+            (None, ']') => depth = depth.saturating_sub(1),
+            (None, '>') if depth == 0 => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Whether `s` is a prolog with no root element after it: only a declaration, processing
+/// instructions, comments and a doctype, as in a file that just carries a DTD.
+pub fn is_prolog_only(s: &str) -> bool {
+    let mut rest = s.trim_start();
+
+    while !rest.is_empty() {
+        let len = if rest.starts_with("<?") {
+            rest.find("?>").map(|i| i + 2)
+        } else if rest.starts_with("<!--") {
+            rest.find("-->").map(|i| i + 3)
+        } else if rest.starts_with("<!") && !rest.starts_with("<![CDATA[") {
+            doctype_len(rest)
+        } else {
+            return false;
+        };
+
+        match len {
+            Some(len) => rest = rest[len..].trim_start(),
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// The root element given to xmlem for a prolog-only document, which it cannot parse without
+/// one. It is removed again from the output.
+pub const PLACEHOLDER_ROOT: &str = "xml-pretty-no-root";
+
+/// The root element a `--fragment` is wrapped in, since xmlem only parses whole documents. It
+/// is removed again from the output.
+pub const FRAGMENT_ROOT: &str = "xml-pretty-fragment";
+
+/// `s` with everything after its XML declaration, if it has one, wrapped in a
+/// [`FRAGMENT_ROOT`] element.
+pub fn wrap_fragment(s: &str) -> String {
+    let content = s.trim_start_matches('\u{feff}');
+    let offset = content
+        .strip_prefix("<?xml")
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_whitespace()))
+        .and_then(|rest| rest.find("?>"))
+        .map_or(0, |end| {
+            s.len() - content.len() + "<?xml".len() + end + "?>".len()
+        });
+    format!(
+        "{}<{root}>{}</{root}>",
+        &s[..offset],
+        &s[offset..],
+        root = FRAGMENT_ROOT
+    )
+}
+
+/// A document with nothing but whitespace in it, which has no root element to format.
+#[derive(Debug)]
+pub struct EmptyDocument;
+
+impl fmt::Display for EmptyDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the document is empty (use --allow-empty to accept it)")
+    }
+}
+
+impl std::error::Error for EmptyDocument {}
+
+/// Content after the end of the root element that XML does not allow there: another element
+/// or non-whitespace text. Comments and processing instructions are allowed.
+#[derive(Debug)]
+pub struct TrailingContent {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for TrailingContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "found content after root element at line {}, column {}",
+            self.line, self.column
+        )
+    }
+}
+
+impl std::error::Error for TrailingContent {}
+
+/// Looks for content after the root element of the source text `s`. Returns `None` if there
+/// is none, or if the markup is too broken to tell, leaving that for the parser to report.
+pub fn trailing_content(s: &str) -> Option<TrailingContent> {
+    let mut offset = root_element_end(s, root_element_offset(s)?)?;
+
+    loop {
+        let rest = &s[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+
+        let len = if trimmed.is_empty() {
+            return None;
+        } else if trimmed.starts_with("<?") {
+            trimmed.find("?>").map(|i| i + 2)
+        } else if trimmed.starts_with("<!--") {
+            trimmed.find("-->").map(|i| i + 3)
+        } else {
+            let (line, column) = line_column(s, offset);
+            return Some(TrailingContent { line, column });
+        }?;
+
+        offset += len;
+    }
+}
+
+/// The line and column of the byte `offset` in `s`, both numbered from 1, counting columns in
+/// characters.
+pub fn line_column(s: &str, offset: usize) -> (usize, usize) {
+    let before = &s[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// The start tags of the elements in the source text `s`, in document order, so that findings
+/// about the parsed tree can point into the source.
+#[derive(Debug)]
+pub struct StartTags<'a> {
+    source: &'a str,
+    offsets: Vec<usize>,
+}
+
+impl<'a> StartTags<'a> {
+    /// Finds the start tags of the root element and everything in it. None are found if the
+    /// markup is too broken to tell where they are.
+    pub fn new(s: &'a str) -> Self {
+        let mut offsets = vec![];
+        let end = root_element_offset(s)
+            .and_then(|start| scan_root_element(s, start, |offset| offsets.push(offset)));
+        if end.is_none() {
+            offsets.clear();
+        }
+        StartTags { source: s, offsets }
+    }
+
+    pub fn count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// The line and column of the `index`th start tag.
+    pub fn element(&self, index: usize) -> Option<(usize, usize)> {
+        let offset = *self.offsets.get(index)?;
+        Some(line_column(self.source, offset))
+    }
+
+    /// The line and column of the attribute `name`, as written including any prefix, in the
+    /// `index`th start tag.
+    pub fn attribute(&self, index: usize, name: &str) -> Option<(usize, usize)> {
+        let offset = *self.offsets.get(index)?;
+        let tag = &self.source[offset..offset + tag_len(&self.source[offset..])?];
+        let found = attribute_offset(tag, name)?;
+        Some(line_column(self.source, offset + found))
+    }
+}
+
+/// The byte offset of the attribute `name` in the start tag `tag`.
+fn attribute_offset(tag: &str, name: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut previous = '<';
+
+    for (i, ch) in tag.char_indices() {
+        match (quote, ch) {
+            (Some(q), ch) if q == ch => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, _)
+                if previous.is_whitespace()
+                    && tag[i..].starts_with(name)
+                    && tag[i + name.len()..].trim_start().starts_with('=') =>
+            {
+                return Some(i);
+            }
+            _ => {}
+        }
+        previous = ch;
+    }
+
+    None
+}
+
+/// Finds the byte offset just past the end tag of the element whose start tag is at `start`.
+fn root_element_end(s: &str, start: usize) -> Option<usize> {
+    scan_root_element(s, start, |_| {})
+}
+
+/// Like [`root_element_end`], passing the offset of each start tag on the way to `on_start_tag`.
+fn scan_root_element(s: &str, start: usize, mut on_start_tag: impl FnMut(usize)) -> Option<usize> {
+    let mut offset = start;
+    let mut depth = 0usize;
+
+    loop {
+        offset += s[offset..].find('<')?;
+        let rest = &s[offset..];
+
+        if rest.starts_with("<!--") {
+            offset += rest.find("-->")? + 3;
+        } else if rest.starts_with("<![CDATA[") {
+            offset += rest.find("]]>")? + 3;
+        } else if rest.starts_with("<?") {
+            offset += rest.find("?>")? + 2;
+        } else if rest.starts_with("</") {
+            offset += rest.find('>')? + 1;
+            depth = depth.checked_sub(1)?;
+            if depth == 0 {
+                return Some(offset);
+            }
+        } else {
+            let len = tag_len(rest)?;
+            let is_empty = rest[..len].ends_with("/>");
+            on_start_tag(offset);
+            offset += len;
+            if !is_empty {
+                depth += 1;
+            } else if depth == 0 {
+                return Some(offset);
+            }
+        }
+    }
+}
+
+/// Rewrites every attribute value in the serialized document `s` with `f`, leaving text,
+/// comments, CDATA sections, processing instructions and the doctype as they are. The value is
+/// passed without its quotes, together with the quote character around it.
+pub fn map_attribute_values(s: &str, f: impl Fn(&str, char) -> String) -> String {
+    let mut output = String::with_capacity(s.len());
+    let mut rest = s;
+
+    while let Some(start) = rest.find('<') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let (len, is_tag) = if rest.starts_with("<!--") {
+            (rest.find("-->").map(|i| i + 3), false)
+        } else if rest.starts_with("<![CDATA[") {
+            (rest.find("]]>").map(|i| i + 3), false)
+        } else if rest.starts_with("<?") {
+            (rest.find("?>").map(|i| i + 2), false)
+        } else if rest.starts_with("<!") {
+            (doctype_len(rest), false)
+        } else {
+            (tag_len(rest), true)
+        };
+        let Some(len) = len else {
+            break;
+        };
+
+        let (markup, after) = rest.split_at(len);
+        if is_tag {
+            let mut quote = None;
+            let mut value_start = 0;
+            for (i, ch) in markup.char_indices() {
+                match (quote, ch) {
+                    (Some(q), ch) if q == ch => {
+                        output.push_str(&f(&markup[value_start..i], q));
+                        output.push(q);
+                        quote = None;
+                    }
+                    (Some(_), _) => {}
+                    (None, '"' | '\'') => {
+                        output.push(ch);
+                        quote = Some(ch);
+                        value_start = i + 1;
+                    }
+                    (None, ch) => output.push(ch),
+                }
+            }
+        } else {
+            output.push_str(markup);
+        }
+        rest = after;
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// Length of the start tag at the beginning of `s`, skipping over `>` in attribute values.
+pub fn tag_len(s: &str) -> Option<usize> {
+    let mut quote = None;
+
+    for (i, ch) in s.char_indices() {
+        match (quote, ch) {
+            (Some(q), ch) if q == ch => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upper(value: &str, _quote: char) -> String {
+        value.to_uppercase()
+    }
+
+    #[test]
+    fn maps_only_attribute_values() {
+        assert_eq!(
+            map_attribute_values(r#"<a x="b>c" y='d"e'>text "q"</a>"#, upper),
+            r#"<a x="B>C" y='D"E'>text "q"</a>"#
+        );
+    }
+
+    #[test]
+    fn leaves_other_markup_alone() {
+        let s = r#"<?pi a="b"?><!DOCTYPE r [<!ENTITY e "x">]><r><!-- c="d" --><![CDATA[<e f="g"/>]]></r>"#;
+        assert_eq!(map_attribute_values(s, upper), s);
+    }
+
+    #[test]
+    fn tag_len_skips_quoted_angle_brackets() {
+        assert_eq!(tag_len(r#"<a x="b>c" y='d>"e'>rest"#), Some(20));
+        assert_eq!(tag_len("<a\n  x=\"1\"\n/>"), Some(13));
+        assert_eq!(tag_len(r#"<a x="unterminated>"#), None);
+    }
+
+    #[test]
+    fn finds_the_root_element_past_the_prolog() {
+        let prolog = concat!(
+            "<?xml version=\"1.0\"?>\n",
+            "<!-- a > b, <r> and <![CDATA[ x ]]> in a comment -->\n",
+            "<?pi <not-root> ?>\n",
+            "<!DOCTYPE r [\n  <!ENTITY e \"]>\">\n  <!ATTLIST r a CDATA '>'>\n]>\n",
+        );
+        let s = format!("{}<r/>", prolog);
+        assert_eq!(root_element_offset(&s), Some(prolog.len()));
+        assert_eq!(root_element_offset("<!-- unterminated <r/>"), None);
+    }
+
+    #[test]
+    fn recognises_a_prolog_only_document() {
+        assert!(is_prolog_only(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE r SYSTEM \"r.dtd\">\n<!-- > -->\n"
+        ));
+        assert!(!is_prolog_only("<!-- c --><r/>"));
+        assert!(!is_prolog_only("<![CDATA[ <!-- ]]>"));
+        assert!(!is_prolog_only("<!-- unterminated"));
+    }
+
+    #[test]
+    fn finds_content_after_the_root_element() {
+        let found = trailing_content("<a>\n  <a/>\n</a>\n<!-- fine --><?pi ok?>\n  <b/>").unwrap();
+        assert_eq!((found.line, found.column), (5, 3));
+        assert_eq!(
+            found.to_string(),
+            "found content after root element at line 5, column 3"
+        );
+        assert_eq!(
+            trailing_content("<a>x</a>é").map(|found| (found.line, found.column)),
+            Some((1, 9))
+        );
+    }
+
+    #[test]
+    fn wraps_a_fragment_after_its_declaration() {
+        assert_eq!(
+            wrap_fragment("<a/>\n<b/>"),
+            "<xml-pretty-fragment><a/>\n<b/></xml-pretty-fragment>"
+        );
+        assert_eq!(
+            wrap_fragment("\u{feff}<?xml version=\"1.0\"?>\ntext <a/>"),
+            "\u{feff}<?xml version=\"1.0\"?><xml-pretty-fragment>\ntext <a/></xml-pretty-fragment>"
+        );
+        // Only a declaration is kept outside, not other processing instructions.
+        assert_eq!(
+            wrap_fragment("<?xml-stylesheet href=\"a\"?><a/>"),
+            "<xml-pretty-fragment><?xml-stylesheet href=\"a\"?><a/></xml-pretty-fragment>"
+        );
+    }
+
+    #[test]
+    fn ignores_markup_like_text_inside_the_root_element() {
+        let s = r#"<a x="</a>"><!-- </a> --><![CDATA[</a><b>]]><?pi </a>?></a>"#;
+        assert!(trailing_content(s).is_none());
+        assert!(trailing_content(&format!("{}\n<!-- after -->\n", s)).is_none());
+        assert!(trailing_content(&format!("{}text", s)).is_some());
+    }
+
+    #[test]
+    fn stops_at_unterminated_markup() {
+        assert_eq!(
+            map_attribute_values(r#"<a x="y"><!-- z"#, upper),
+            r#"<a x="Y"><!-- z"#
+        );
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        assert_eq!(line_column("<a>", 0), (1, 1));
+        assert_eq!(line_column("<a>\n  <b/>", 6), (2, 3));
+        assert_eq!(line_column("<a>é\n", 5), (1, 5));
+        assert_eq!(line_column("<a>é<b/>", 5), (1, 5));
+    }
+
+    #[test]
+    fn finds_start_tags_in_document_order() {
+        let s = "<?xml version=\"1.0\"?>\n<!-- <x/> -->\n<r>\n  <a><b/></a><![CDATA[<y>]]>\n  <?pi <z/>?><c/>\n</r>\n";
+        let tags = StartTags::new(s);
+        assert_eq!(tags.count(), 4);
+        let found = (0..5).map(|i| tags.element(i)).collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                Some((3, 1)),
+                Some((4, 3)),
+                Some((4, 6)),
+                Some((5, 14)),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_no_start_tags_in_broken_markup() {
+        assert_eq!(StartTags::new("<r><a x=\"1></r>").count(), 0);
+        assert_eq!(StartTags::new("<?xml version=\"1.0\"?>").count(), 0);
+    }
+
+    #[test]
+    fn finds_attributes_by_name_outside_values() {
+        let s = "<r a=\"x b='1'\"\n   ns:b = '2' b=\"3\"/>";
+        let tags = StartTags::new(s);
+        assert_eq!(tags.attribute(0, "a"), Some((1, 4)));
+        assert_eq!(tags.attribute(0, "ns:b"), Some((2, 4)));
+        assert_eq!(tags.attribute(0, "b"), Some((2, 15)));
+        assert_eq!(tags.attribute(0, "c"), None);
+        assert_eq!(tags.attribute(1, "a"), None);
+    }
+}
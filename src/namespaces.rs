@@ -0,0 +1,369 @@
+use std::str::FromStr;
+
+use xmlem::{Document, Element};
+
+use crate::outline::{write_location, Locator};
+
+/// What to do with a prefix such as `foo:bar` that has no `xmlns:foo` declaration in scope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UndeclaredPrefixPolicy {
+    /// Refuse the document.
+    #[default]
+    Error,
+    /// Pass it through, treating `foo:` as part of the name.
+    Allow,
+    /// Add a placeholder `xmlns:foo` declaration on the element that uses it.
+    Synthesize,
+}
+
+impl FromStr for UndeclaredPrefixPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(UndeclaredPrefixPolicy::Error),
+            "allow" => Ok(UndeclaredPrefixPolicy::Allow),
+            "synthesize" => Ok(UndeclaredPrefixPolicy::Synthesize),
+            other => Err(format!(
+                "expected 'error', 'allow' or 'synthesize', got '{}'",
+                other
+            )),
+        }
+    }
+}
+
+/// A use of a prefix with no declaration in scope.
+#[derive(Debug)]
+pub struct UndeclaredPrefix {
+    pub prefix: String,
+    /// Path of the element using it, e.g. `/root/item`.
+    pub path: String,
+}
+
+impl std::fmt::Display for UndeclaredPrefix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "undeclared namespace prefix '{}' at {}",
+            self.prefix, self.path
+        )
+    }
+}
+
+impl std::error::Error for UndeclaredPrefix {}
+
+/// The namespace given to declarations added under [`UndeclaredPrefixPolicy::Synthesize`].
+fn placeholder_namespace(prefix: &str) -> String {
+    format!("urn:xml-pretty:undeclared:{}", prefix)
+}
+
+/// Finds element and attribute prefixes that are used without being declared and applies
+/// `policy` to them. The reserved `xml` and `xmlns` prefixes are always in scope. Returns
+/// the undeclared prefixes that were let through, so they can be reported as warnings.
+pub fn check_prefixes(
+    doc: &mut Document,
+    policy: UndeclaredPrefixPolicy,
+) -> Result<Vec<UndeclaredPrefix>, UndeclaredPrefix> {
+    let mut scope = vec!["xml".to_string(), "xmlns".to_string()];
+    let mut undeclared = vec![];
+    let root = doc.root();
+    let path = format!("/{}", root.name(doc));
+    check_element(doc, root, &path, &mut scope, policy, &mut undeclared)?;
+    Ok(undeclared)
+}
+
+fn check_element(
+    doc: &mut Document,
+    element: Element,
+    path: &str,
+    scope: &mut Vec<String>,
+    policy: UndeclaredPrefixPolicy,
+    undeclared: &mut Vec<UndeclaredPrefix>,
+) -> Result<(), UndeclaredPrefix> {
+    let scope_len = scope.len();
+
+    let attr_names = element
+        .attributes(doc)
+        .keys()
+        .map(|name| name.prefixed_name().to_string())
+        .collect::<Vec<_>>();
+    scope.extend(
+        attr_names
+            .iter()
+            .filter_map(|name| name.strip_prefix("xmlns:"))
+            .map(str::to_string),
+    );
+
+    let used = element
+        .prefix(doc)
+        .into_iter()
+        .chain(
+            attr_names
+                .iter()
+                .filter_map(|name| name.split_once(':').map(|x| x.0)),
+        )
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    for prefix in used {
+        if scope.contains(&prefix) {
+            continue;
+        }
+
+        let found = UndeclaredPrefix {
+            prefix,
+            path: path.to_string(),
+        };
+
+        match policy {
+            UndeclaredPrefixPolicy::Error => return Err(found),
+            UndeclaredPrefixPolicy::Allow => {}
+            UndeclaredPrefixPolicy::Synthesize => {
+                element.set_attribute(
+                    doc,
+                    format!("xmlns:{}", found.prefix).as_str(),
+                    &placeholder_namespace(&found.prefix),
+                );
+            }
+        }
+
+        scope.push(found.prefix.clone());
+        undeclared.push(found);
+    }
+
+    for child in element.children(doc) {
+        let child_path = format!("{}/{}", path, child.name(doc));
+        check_element(doc, child, &child_path, scope, policy, undeclared)?;
+    }
+
+    scope.truncate(scope_len);
+    Ok(())
+}
+
+/// An unprefixed element in no namespace, in a document whose root is in the expected default
+/// namespace. This is usually an `xmlns=""` left over from pasting a snippet.
+#[derive(Debug)]
+pub struct MissingDefaultNamespace {
+    pub path: String,
+    pub namespace: String,
+    /// Where the element is in the source.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for MissingDefaultNamespace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_location(f, self.location)?;
+        write!(
+            f,
+            "element {} is in no namespace, not '{}'",
+            self.path, self.namespace
+        )
+    }
+}
+
+/// Finds the unprefixed elements that an `xmlns=""` takes out of `namespace`, where it would
+/// otherwise be inherited. Nothing is reported unless the root element itself declares
+/// `namespace` as its default.
+pub fn check_default_namespace(
+    doc: &Document,
+    namespace: &str,
+    locator: &Locator,
+) -> Vec<MissingDefaultNamespace> {
+    let mut found = vec![];
+    let root = doc.root();
+    if root.attribute(doc, "xmlns") == Some(namespace) {
+        let path = format!("/{}", root.name(doc));
+        check_default_element(doc, locator, root, &path, namespace, namespace, &mut found);
+    }
+    found
+}
+
+fn check_default_element(
+    doc: &Document,
+    locator: &Locator,
+    element: Element,
+    path: &str,
+    namespace: &str,
+    inherited: &str,
+    found: &mut Vec<MissingDefaultNamespace>,
+) {
+    let default = match element.attribute(doc, "xmlns") {
+        // An `xmlns=""` inside some other namespace's content is not ours to judge.
+        Some("") if inherited != namespace => inherited,
+        Some(default) => default,
+        None => inherited,
+    };
+
+    if default.is_empty() && element.prefix(doc).is_none() {
+        found.push(MissingDefaultNamespace {
+            path: path.to_string(),
+            namespace: namespace.to_string(),
+            location: locator.element(element),
+        });
+    }
+
+    for child in element.children(doc) {
+        let child_path = format!("{}/{}", path, child.name(doc));
+        check_default_element(doc, locator, child, &child_path, namespace, default, found);
+    }
+}
+
+/// Puts the elements found by [`check_default_namespace`] back into `namespace` by removing
+/// the `xmlns=""` declarations that override it.
+pub fn fix_default_namespace(doc: &mut Document, namespace: &str) {
+    let root = doc.root();
+    if root.attribute(doc, "xmlns") == Some(namespace) {
+        fix_default_element(doc, root, namespace, namespace.to_string());
+    }
+}
+
+fn fix_default_element(doc: &mut Document, element: Element, namespace: &str, inherited: String) {
+    if inherited == namespace && element.attribute(doc, "xmlns") == Some("") {
+        element.remove_attribute(doc, "xmlns");
+    }
+
+    let default = match element.attribute(doc, "xmlns") {
+        Some("") | None => inherited,
+        Some(default) => default.to_string(),
+    };
+
+    for child in element.children(doc) {
+        fix_default_element(doc, child, namespace, default.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNDECLARED: &str = "<r xml:lang=\"en\"><a:x/><b:y xmlns:b=\"urn:b\" c:z=\"1\"/></r>";
+
+    fn checked(xml: &str, policy: UndeclaredPrefixPolicy) -> (String, Result<Vec<String>, String>) {
+        let mut doc = Document::from_str(xml).unwrap();
+        let result = check_prefixes(&mut doc, policy)
+            .map(|found| found.iter().map(ToString::to_string).collect())
+            .map_err(|e| e.to_string());
+        (doc.to_string(), result)
+    }
+
+    #[test]
+    fn policies_parse() {
+        assert_eq!("error".parse(), Ok(UndeclaredPrefixPolicy::Error));
+        assert_eq!("allow".parse(), Ok(UndeclaredPrefixPolicy::Allow));
+        assert_eq!("synthesize".parse(), Ok(UndeclaredPrefixPolicy::Synthesize));
+        assert_eq!(
+            "ignore".parse::<UndeclaredPrefixPolicy>(),
+            Err("expected 'error', 'allow' or 'synthesize', got 'ignore'".to_string())
+        );
+    }
+
+    #[test]
+    fn declared_and_reserved_prefixes_are_in_scope() {
+        let xml =
+            "<r xmlns:a=\"urn:a\" xml:lang=\"en\"><a:x xmlns:xsi=\"urn:xsi\" xsi:t=\"1\"/></r>";
+        for policy in [
+            UndeclaredPrefixPolicy::Error,
+            UndeclaredPrefixPolicy::Allow,
+            UndeclaredPrefixPolicy::Synthesize,
+        ] {
+            assert_eq!(checked(xml, policy), (xml.to_string(), Ok(vec![])));
+        }
+    }
+
+    #[test]
+    fn error_stops_at_the_first_undeclared_prefix() {
+        assert_eq!(
+            checked(UNDECLARED, UndeclaredPrefixPolicy::Error),
+            (
+                UNDECLARED.to_string(),
+                Err("undeclared namespace prefix 'a' at /r/a:x".to_string())
+            )
+        );
+    }
+
+    #[test]
+    fn allow_reports_each_undeclared_prefix_and_changes_nothing() {
+        assert_eq!(
+            checked(UNDECLARED, UndeclaredPrefixPolicy::Allow),
+            (
+                UNDECLARED.to_string(),
+                Ok(vec![
+                    "undeclared namespace prefix 'a' at /r/a:x".to_string(),
+                    "undeclared namespace prefix 'c' at /r/b:y".to_string(),
+                ])
+            )
+        );
+    }
+
+    #[test]
+    fn synthesize_declares_placeholder_namespaces() {
+        let (xml, found) = checked(UNDECLARED, UndeclaredPrefixPolicy::Synthesize);
+        assert_eq!(found.unwrap().len(), 2);
+        assert_eq!(
+            xml,
+            "<r xml:lang=\"en\"><a:x xmlns:a=\"urn:xml-pretty:undeclared:a\"/>\
+             <b:y xmlns:b=\"urn:b\" c:z=\"1\" xmlns:c=\"urn:xml-pretty:undeclared:c\"/></r>"
+        );
+
+        // The added declarations leave the document with nothing undeclared.
+        assert_eq!(
+            checked(&xml, UndeclaredPrefixPolicy::Error),
+            (xml.clone(), Ok(vec![]))
+        );
+    }
+
+    #[test]
+    fn a_synthesized_declaration_covers_the_descendants() {
+        let (xml, found) = checked(
+            "<r><a:x><a:y/></a:x></r>",
+            UndeclaredPrefixPolicy::Synthesize,
+        );
+        assert_eq!(
+            found,
+            Ok(vec!["undeclared namespace prefix 'a' at /r/a:x".to_string()])
+        );
+        assert_eq!(
+            xml,
+            "<r><a:x xmlns:a=\"urn:xml-pretty:undeclared:a\"><a:y/></a:x></r>"
+        );
+    }
+
+    #[test]
+    fn resets_of_the_default_namespace_are_found_and_removed() {
+        let source = "<r xmlns=\"urn:x\"><a xmlns=\"\"><b/></a><c/><o xmlns=\"urn:o\"><d xmlns=\"\"/></o></r>";
+        let mut doc = Document::from_str(source).unwrap();
+
+        let locator = Locator::new(&doc, source);
+        let found = check_default_namespace(&doc, "urn:x", &locator)
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                "1:18: element /r/a is in no namespace, not 'urn:x'",
+                "1:30: element /r/a/b is in no namespace, not 'urn:x'",
+            ]
+        );
+
+        // The reset inside another namespace's content is left alone.
+        fix_default_namespace(&mut doc, "urn:x");
+        assert_eq!(
+            doc.to_string(),
+            "<r xmlns=\"urn:x\"><a><b/></a><c/><o xmlns=\"urn:o\"><d xmlns=\"\"/></o></r>"
+        );
+        let source = doc.to_string();
+        let locator = Locator::new(&doc, &source);
+        assert!(check_default_namespace(&doc, "urn:x", &locator).is_empty());
+    }
+
+    #[test]
+    fn other_default_namespaces_are_not_fixed() {
+        let source = "<r xmlns=\"urn:y\"><a xmlns=\"\"/></r>";
+        let mut doc = Document::from_str(source).unwrap();
+        let locator = Locator::new(&doc, source);
+        assert!(check_default_namespace(&doc, "urn:x", &locator).is_empty());
+        fix_default_namespace(&mut doc, "urn:x");
+        assert_eq!(doc.to_string(), source);
+    }
+}
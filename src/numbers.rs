@@ -0,0 +1,465 @@
+use std::{borrow::Cow, str::FromStr};
+
+use xmlem::{Document, Element};
+
+use crate::outline::{element_path, write_location, Locator};
+
+/// The attributes `--round-numbers` rounds when no `attrs=` list is given: SVG geometry.
+const DEFAULT_ATTRIBUTES: &[&str] = &[
+    "d",
+    "points",
+    "x",
+    "y",
+    "x1",
+    "y1",
+    "x2",
+    "y2",
+    "cx",
+    "cy",
+    "r",
+    "rx",
+    "ry",
+    "dx",
+    "dy",
+    "width",
+    "height",
+    "transform",
+    "viewBox",
+    "stroke-width",
+];
+
+/// CSS units, which a number may be followed by and still be rounded, as in `12.5px`.
+const UNITS: &[&str] = &[
+    "px", "pt", "pc", "cm", "mm", "in", "em", "ex", "ch", "rem", "vw", "vh", "vmin", "vmax", "deg",
+    "grad", "rad", "turn", "s", "ms", "hz", "khz",
+];
+
+/// An exponent beyond this is not expanded, so a value like `1e999999` is left as written.
+const MAX_EXPONENT: i64 = 1000;
+
+/// `PRECISION[:attrs=NAME,...]`: the numbers in the values of the named attributes, as written
+/// including any prefix, are rounded to `PRECISION` decimal places.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rounding {
+    pub precision: usize,
+    pub attributes: Vec<String>,
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || {
+            format!(
+                "expected 'PRECISION' or 'PRECISION:attrs=NAME,...', got '{}'",
+                s
+            )
+        };
+
+        let (precision, attributes) = match s.split_once(':') {
+            Some((precision, attrs)) => {
+                let attributes = attrs
+                    .strip_prefix("attrs=")
+                    .ok_or_else(error)?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                if attributes.is_empty() {
+                    return Err(error());
+                }
+                (precision, attributes)
+            }
+            None => (
+                s,
+                DEFAULT_ATTRIBUTES
+                    .iter()
+                    .map(|name| name.to_string())
+                    .collect(),
+            ),
+        };
+
+        Ok(Rounding {
+            precision: precision.parse().map_err(|_| error())?,
+            attributes,
+        })
+    }
+}
+
+impl Rounding {
+    fn applies_to(&self, name: &str) -> bool {
+        self.attributes.iter().any(|n| n == name)
+    }
+
+    /// `value` with its numbers rounded, and the first number changed along with its rounded
+    /// form, or `None` if nothing changes.
+    fn round(&self, value: &str) -> Option<(String, (String, String))> {
+        let mut output = String::with_capacity(value.len());
+        let mut first_change = None;
+        // Whether the last thing written was a number with a decimal point, if it was a number.
+        let mut last_number: Option<bool> = None;
+        let bytes = value.as_bytes();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if bytes[i].is_ascii_alphabetic() || bytes[i] == b'_' || bytes[i] == b'#' {
+                // A single letter is a path command, as in `M10` or `l-5`. Anything longer is
+                // an identifier such as `grad1` or `url(#a2)`, and its digits are not numbers.
+                let end = i + run_len(&bytes[i..], |b| b.is_ascii_alphabetic());
+                let end = if end == i + 1 && bytes[i].is_ascii_alphabetic() {
+                    end
+                } else {
+                    i + run_len(&bytes[i..], |b| {
+                        b.is_ascii_alphanumeric() || matches!(b, b'_' | b'#' | b'.' | b'-')
+                    })
+                };
+                output.push_str(&value[i..end]);
+                last_number = None;
+                i = end;
+                continue;
+            }
+
+            let Some(len) = number_len(&bytes[i..]) else {
+                let len = value[i..].chars().next().map_or(1, char::len_utf8);
+                output.push_str(&value[i..i + len]);
+                last_number = None;
+                i += len;
+                continue;
+            };
+            let number = &value[i..i + len];
+            i += len;
+
+            // Letters after a number are only split off if they are a unit or a path command,
+            // as in `12.5px` or `10.5L`; otherwise, as in `2.5GB`, the whole word is left alone.
+            let letters = run_len(&bytes[i..], |b| b.is_ascii_alphabetic());
+            let suffix = &value[i..i + letters];
+            let is_unit = UNITS.iter().any(|unit| unit.eq_ignore_ascii_case(suffix))
+                && !bytes
+                    .get(i + letters)
+                    .is_some_and(|b| b.is_ascii_digit() || *b == b'.');
+            let is_separable = letters <= 1 || is_unit;
+            if !is_separable {
+                let end = i + run_len(&bytes[i..], |b| b.is_ascii_alphanumeric() || b == b'_');
+                output.push_str(number);
+                output.push_str(&value[i..end]);
+                last_number = None;
+                i = end;
+                continue;
+            }
+
+            let rounded = round_number(number, self.precision)
+                .filter(|rounded| rounded != number)
+                .map(Cow::Owned)
+                .unwrap_or(Cow::Borrowed(number));
+
+            // Numbers can follow each other without a separator, as in `1.5.5` or `5-2`.
+            // Rounding can take away what kept them apart, so a space is put back if needed.
+            if let Some(has_point) = last_number {
+                if rounded.starts_with(|c: char| c.is_ascii_digit())
+                    || (rounded.starts_with('.') && !has_point)
+                {
+                    output.push(' ');
+                }
+            }
+            output.push_str(&rounded);
+            last_number = Some(rounded.contains('.'));
+
+            if let Cow::Owned(rounded) = rounded {
+                first_change.get_or_insert_with(|| (number.to_string(), rounded));
+            }
+        }
+
+        first_change.map(|change| (output, change))
+    }
+}
+
+/// The length of the run of bytes at the start of `bytes` that match `f`.
+fn run_len(bytes: &[u8], f: impl Fn(u8) -> bool) -> usize {
+    bytes.iter().position(|b| !f(*b)).unwrap_or(bytes.len())
+}
+
+/// The length of the number at the start of `bytes`, if there is one: an optional sign, digits
+/// with an optional fraction, and an optional exponent. `1.5.5` is two numbers, as in SVG.
+fn number_len(bytes: &[u8]) -> Option<usize> {
+    let mut i = usize::from(matches!(bytes.first(), Some(b'+' | b'-')));
+    let integer = run_len(&bytes[i..], |b| b.is_ascii_digit());
+    i += integer;
+    let mut fraction = 0;
+    if bytes.get(i) == Some(&b'.') {
+        fraction = run_len(&bytes[i + 1..], |b| b.is_ascii_digit());
+        if integer > 0 || fraction > 0 {
+            i += 1 + fraction;
+        }
+    }
+    if integer == 0 && fraction == 0 {
+        return None;
+    }
+
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let sign = usize::from(matches!(bytes.get(i + 1), Some(b'+' | b'-')));
+        let digits = run_len(&bytes[i + 1 + sign..], |b| b.is_ascii_digit());
+        if digits > 0 {
+            i += 1 + sign + digits;
+        }
+    }
+    Some(i)
+}
+
+/// `number` rounded half away from zero to `precision` decimal places, without trailing zeros
+/// in the fraction, leading zeros in the integer part, a `+` sign or an exponent. The digits
+/// are rounded as written, so no floating point error is introduced. An exponent whose
+/// expansion would be longer than `number` itself is kept, and `None` returned.
+fn round_number(number: &str, precision: usize) -> Option<String> {
+    let (is_negative, unsigned) = match number.as_bytes()[0] {
+        b'-' => (true, &number[1..]),
+        b'+' => (false, &number[1..]),
+        _ => (false, number),
+    };
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(e) => (&unsigned[..e], unsigned[e + 1..].parse::<i64>().ok()?),
+        None => (unsigned, 0),
+    };
+    if exponent.abs() > MAX_EXPONENT {
+        return None;
+    }
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    // The digits, with the decimal point after `point` of them once the exponent is applied.
+    let mut digits = format!("{}{}", integer, fraction).into_bytes();
+    let mut point = integer.len() as i64 + exponent;
+    if point < 0 {
+        let zeros = (-point) as usize;
+        digits.splice(0..0, std::iter::repeat_n(b'0', zeros));
+        point = 0;
+    }
+    let mut point = point as usize;
+    if digits.len() < point {
+        digits.resize(point, b'0');
+    }
+
+    let kept = point.saturating_add(precision).min(digits.len());
+    let rounds_up = digits.get(kept).is_some_and(|d| *d >= b'5');
+    digits.truncate(kept);
+    if rounds_up {
+        let mut carry = true;
+        for digit in digits.iter_mut().rev() {
+            if *digit == b'9' {
+                *digit = b'0';
+            } else {
+                *digit += 1;
+                carry = false;
+                break;
+            }
+        }
+        if carry {
+            digits.insert(0, b'1');
+            point += 1;
+        }
+    }
+
+    let digits = String::from_utf8(digits).expect("digits are ASCII");
+    let fraction = digits[point..].trim_end_matches('0');
+    // A leading zero is only left out if `number` left it out, so `.5` stays `.5`.
+    let integer = match digits[..point].trim_start_matches('0') {
+        "" if fraction.is_empty() || !mantissa.starts_with('.') => "0",
+        integer => integer,
+    };
+    let is_zero = integer == "0" && fraction.is_empty();
+
+    let mut rounded = String::with_capacity(digits.len() + 2);
+    if is_negative && !is_zero {
+        rounded.push('-');
+    }
+    rounded.push_str(integer);
+    if !fraction.is_empty() {
+        rounded.push('.');
+        rounded.push_str(fraction);
+    }
+
+    if exponent != 0 && rounded.len() > number.len() {
+        return None;
+    }
+    Some(rounded)
+}
+
+/// An attribute value with a number that `round_numbers` would round.
+#[derive(Debug)]
+pub struct UnroundedNumber {
+    pub path: String,
+    pub name: String,
+    pub number: String,
+    pub expected: String,
+    /// Where the attribute is in the source.
+    pub location: Option<(usize, usize)>,
+}
+
+impl std::fmt::Display for UnroundedNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_location(f, self.location)?;
+        write!(
+            f,
+            "attribute '{}' of {} has {}, expected {}",
+            self.name, self.path, self.number, self.expected
+        )
+    }
+}
+
+/// Finds the attribute values that `round_numbers` would change, without changing anything.
+/// Each value is reported once, by its first number that is not rounded.
+pub fn check_rounded(
+    doc: &Document,
+    rounding: &Rounding,
+    locator: &Locator,
+) -> Vec<UnroundedNumber> {
+    let root = doc.root();
+    let mut found = vec![];
+
+    for element in std::iter::once(root).chain(root.walk(doc)) {
+        for (name, value) in element.attributes(doc) {
+            if !rounding.applies_to(name.prefixed_name()) {
+                continue;
+            }
+            if let Some((_, (number, expected))) = rounding.round(value) {
+                found.push(UnroundedNumber {
+                    path: element_path(doc, element),
+                    name: name.prefixed_name().to_string(),
+                    number,
+                    expected,
+                    location: locator.attribute(element, name.prefixed_name()),
+                });
+            }
+        }
+    }
+
+    found
+}
+
+/// Rounds the numbers in the values of the attributes `rounding` names, leaving everything else
+/// in those values, such as path commands, separators and identifiers, as it is.
+pub fn round_numbers(doc: &mut Document, rounding: &Rounding) {
+    let root = doc.root();
+    let elements = std::iter::once(root)
+        .chain(root.walk(doc))
+        .collect::<Vec<Element>>();
+
+    for element in elements {
+        let rounded = element
+            .attributes(doc)
+            .iter()
+            .filter(|(name, _)| rounding.applies_to(name.prefixed_name()))
+            .filter_map(|(name, value)| Some((name.clone(), rounding.round(value)?.0)))
+            .collect::<Vec<_>>();
+
+        for (name, value) in rounded {
+            element.set_attribute(doc, name, &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round(number: &str, precision: usize) -> Option<String> {
+        round_number(number, precision)
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        assert_eq!(round("2.5", 0).as_deref(), Some("3"));
+        assert_eq!(round("-2.5", 0).as_deref(), Some("-3"));
+        assert_eq!(round("1.005", 2).as_deref(), Some("1.01"));
+        assert_eq!(round("1.004", 2).as_deref(), Some("1"));
+        assert_eq!(round("32.25806451612903", 2).as_deref(), Some("32.26"));
+    }
+
+    #[test]
+    fn carries_into_new_digits() {
+        assert_eq!(round("9.96", 1).as_deref(), Some("10"));
+        assert_eq!(round("-99.95", 1).as_deref(), Some("-100"));
+    }
+
+    #[test]
+    fn handles_negatives_and_negative_zero() {
+        assert_eq!(round("-1.234", 1).as_deref(), Some("-1.2"));
+        assert_eq!(round("-0.04", 1).as_deref(), Some("0"));
+        assert_eq!(round("+1.50", 2).as_deref(), Some("1.5"));
+    }
+
+    #[test]
+    fn strips_zeros() {
+        assert_eq!(round("007.100", 3).as_deref(), Some("7.1"));
+        assert_eq!(round("10", 2).as_deref(), Some("10"));
+        assert_eq!(round("0.000", 2).as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn keeps_a_missing_leading_zero_missing() {
+        assert_eq!(round(".567", 2).as_deref(), Some(".57"));
+        assert_eq!(round("-.567", 1).as_deref(), Some("-.6"));
+        assert_eq!(round("0.567", 1).as_deref(), Some("0.6"));
+        assert_eq!(round(".96", 1).as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn expands_short_exponents() {
+        assert_eq!(round("1.2345e2", 1).as_deref(), Some("123.5"));
+        assert_eq!(round("5E-3", 2).as_deref(), Some("0.01"));
+        assert_eq!(round("-1.5e-1", 1).as_deref(), Some("-0.2"));
+        assert_eq!(round("1e-9", 2).as_deref(), Some("0"));
+    }
+
+    #[test]
+    fn keeps_exponents_that_would_grow() {
+        assert_eq!(round("1e20", 2), None);
+        assert_eq!(round("1e999999", 2), None);
+    }
+
+    #[test]
+    fn huge_precision_keeps_every_digit() {
+        assert_eq!(round("1.5", usize::MAX).as_deref(), Some("1.5"));
+        assert_eq!(round("-0.25e1", usize::MAX).as_deref(), Some("-2.5"));
+    }
+
+    fn rounding(precision: usize) -> Rounding {
+        Rounding {
+            precision,
+            attributes: vec!["d".to_string()],
+        }
+    }
+
+    #[test]
+    fn rounds_inside_path_data() {
+        let (value, first) = rounding(1).round("M10.25,20.75L-3.14159 4e1z").unwrap();
+        assert_eq!(value, "M10.3,20.8L-3.1 40z");
+        assert_eq!(first, ("10.25".to_string(), "10.3".to_string()));
+    }
+
+    #[test]
+    fn keeps_numbers_apart() {
+        assert_eq!(rounding(2).round("-2.71828.5").unwrap().0, "-2.72.5");
+        assert_eq!(rounding(0).round("1.5.5").unwrap().0, "2 1");
+    }
+
+    #[test]
+    fn leaves_identifiers_and_units_alone() {
+        assert_eq!(rounding(1).round("url(#grad1.25)"), None);
+        assert_eq!(rounding(1).round("3.25GB"), None);
+        assert_eq!(rounding(1).round("12.25px").unwrap().0, "12.3px");
+    }
+
+    #[test]
+    fn parses_settings() {
+        assert_eq!("2".parse::<Rounding>().unwrap().precision, 2);
+        assert_eq!(
+            "1:attrs=x, y".parse::<Rounding>().unwrap(),
+            Rounding {
+                precision: 1,
+                attributes: vec!["x".to_string(), "y".to_string()],
+            }
+        );
+        assert!("2:attrs=".parse::<Rounding>().is_err());
+        assert!("two".parse::<Rounding>().is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt::Write;
+
+use xmlem::{Document, Element};
+
+use crate::markup::StartTags;
+
+/// Lists every element of `doc` by its path from the root, one per line, e.g.
+/// `/root/section/item`. The root is at depth 1; elements deeper than `max_depth` are
+/// omitted. With `include_attrs`, each line is followed by the element's attributes.
+pub fn outline(doc: &Document, max_depth: Option<usize>, include_attrs: bool) -> String {
+    let mut output = String::new();
+    outline_element(
+        &mut output,
+        doc,
+        doc.root(),
+        "",
+        1,
+        max_depth,
+        include_attrs,
+    );
+    output
+}
+
+fn outline_element(
+    output: &mut String,
+    doc: &Document,
+    element: Element,
+    parent_path: &str,
+    depth: usize,
+    max_depth: Option<usize>,
+    include_attrs: bool,
+) {
+    if max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return;
+    }
+
+    let path = format!("{}/{}", parent_path, element.name(doc));
+    output.push_str(&path);
+
+    if include_attrs {
+        for (name, value) in element.attributes(doc) {
+            let _ = write!(output, " {}=\"{}\"", name, value.escape_debug());
+        }
+    }
+
+    output.push('\n');
+
+    for child in element.children(doc) {
+        outline_element(
+            output,
+            doc,
+            child,
+            &path,
+            depth + 1,
+            max_depth,
+            include_attrs,
+        );
+    }
+}
+
+/// The path of `element` from the root, in the same form as the outline, e.g. `/root/item`.
+pub fn element_path(doc: &Document, element: Element) -> String {
+    let mut names = vec![element.name(doc)];
+    let mut current = element;
+    while let Some(parent) = current.parent(doc) {
+        names.push(parent.name(doc));
+        current = parent;
+    }
+    names.reverse();
+    format!("/{}", names.join("/"))
+}
+
+/// How many levels of elements `doc` has, counting the root as 1.
+pub fn depth(doc: &Document) -> usize {
+    let mut deepest = 0;
+    let mut stack = vec![(doc.root(), 1)];
+    while let Some((element, depth)) = stack.pop() {
+        deepest = deepest.max(depth);
+        stack.extend(
+            element
+                .children(doc)
+                .into_iter()
+                .map(|child| (child, depth + 1)),
+        );
+    }
+    deepest
+}
+
+/// Documents nested deeper than this are refused: xmlem formats them recursively, and a few
+/// thousand levels is enough to overflow the stack.
+pub const MAX_DEPTH: usize = 1024;
+
+/// A document whose elements are nested more than [`MAX_DEPTH`] levels deep.
+#[derive(Debug)]
+pub struct TooDeep {
+    pub depth: usize,
+}
+
+impl std::fmt::Display for TooDeep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "elements are nested {} levels deep, more than the {} xml-pretty supports",
+            self.depth, MAX_DEPTH
+        )
+    }
+}
+
+impl std::error::Error for TooDeep {}
+
+/// Writes the `line:column: ` a finding at `location` starts with, if its location is known.
+pub fn write_location(
+    f: &mut std::fmt::Formatter<'_>,
+    location: Option<(usize, usize)>,
+) -> std::fmt::Result {
+    match location {
+        Some((line, column)) => write!(f, "{}:{}: ", line, column),
+        None => Ok(()),
+    }
+}
+
+/// Finds the elements of a parsed document in its source text, for findings to say where they
+/// are. Nothing is found if the source has a different number of elements than the tree, as
+/// when entities expand to markup.
+pub struct Locator<'a> {
+    elements: Vec<Element>,
+    tags: StartTags<'a>,
+}
+
+impl<'a> Locator<'a> {
+    pub fn new(doc: &Document, source: &'a str) -> Self {
+        let root = doc.root();
+        let elements = std::iter::once(root)
+            .chain(root.walk(doc))
+            .collect::<Vec<_>>();
+        let tags = StartTags::new(source);
+        Locator {
+            elements: if tags.count() == elements.len() {
+                elements
+            } else {
+                vec![]
+            },
+            tags,
+        }
+    }
+
+    fn index(&self, element: Element) -> Option<usize> {
+        self.elements.iter().position(|e| *e == element)
+    }
+
+    /// The line and column of `element`'s start tag.
+    pub fn element(&self, element: Element) -> Option<(usize, usize)> {
+        self.tags.element(self.index(element)?)
+    }
+
+    /// The line and column of the attribute `name` of `element`, or of its start tag if the
+    /// attribute is not written there.
+    pub fn attribute(&self, element: Element, name: &str) -> Option<(usize, usize)> {
+        let index = self.index(element)?;
+        self.tags
+            .attribute(index, name)
+            .or_else(|| self.tags.element(index))
+    }
+}
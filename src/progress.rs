@@ -0,0 +1,32 @@
+use std::io::{self, IsTerminal};
+
+/// A `formatted X/Y` line on stderr for `--progress`, redrawn in place. It is only shown when
+/// stderr is a terminal, so redirected stderr and piped stdout never see it.
+pub struct Progress {
+    verb: &'static str,
+    total: usize,
+    is_shown: bool,
+}
+
+impl Progress {
+    pub fn new(verb: &'static str, total: usize, is_requested: bool) -> Self {
+        Progress {
+            verb,
+            total,
+            is_shown: is_requested && io::stderr().is_terminal(),
+        }
+    }
+
+    pub fn update(&self, done: usize) {
+        if self.is_shown {
+            eprint!("\r\x1b[K{} {}/{}", self.verb, done, self.total);
+        }
+    }
+
+    /// Erases the line, so other messages and output can be written.
+    pub fn clear(&self) {
+        if self.is_shown {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
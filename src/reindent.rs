@@ -0,0 +1,438 @@
+use std::collections::HashMap;
+
+/// What the start of a line is inside of, which decides whether its indentation may change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LineKind {
+    /// Element content or the prolog, indented by depth.
+    Content,
+    /// Between the attributes of a start tag that began on an earlier line, aligned to it.
+    TagContinuation { tag_line: usize },
+    /// Inside a comment, CDATA section, processing instruction, doctype, attribute value or
+    /// `xml:space="preserve"` element, where whitespace is data or deliberate layout.
+    Protected,
+}
+
+/// Where the scanner is in the markup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Content,
+    Tag { quote: Option<char> },
+    Comment,
+    CData,
+    Pi,
+    Doctype { brackets: usize },
+}
+
+/// Lines left alone by [`reindent`] because their indentation is not a whole number of levels.
+#[derive(Debug)]
+pub struct Irregular {
+    pub count: usize,
+    /// The first of them, numbered from 1.
+    pub first_line: usize,
+}
+
+impl std::fmt::Display for Irregular {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "left {} line{} with irregular indentation as {}, the first at line {}",
+            self.count,
+            if self.count == 1 { "" } else { "s" },
+            if self.count == 1 { "it is" } else { "they are" },
+            self.first_line
+        )
+    }
+}
+
+/// Rewrites the indentation of `source` to `unit` per level, leaving every other byte as it is.
+/// The current indentation unit is inferred as the most common step between the indentation
+/// of consecutive lines that start with markup, and each line's level is its indentation
+/// divided by it. Lines inside comments, CDATA sections, processing instructions and
+/// `xml:space="preserve"` elements are not touched. The attribute lines of a start tag that
+/// spans lines move with the line the tag starts on.
+///
+/// Lines whose indentation mixes tabs and spaces or is not a whole number of levels are left
+/// alone and returned as [`Irregular`], to be reported.
+pub fn reindent(source: &str, unit: &str) -> (String, Option<Irregular>) {
+    let lines = source.split('\n').collect::<Vec<_>>();
+    let kinds = line_kinds(source);
+    let leading = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+
+    let step = infer_step(
+        lines
+            .iter()
+            .zip(kinds.iter())
+            .filter(|(line, kind)| {
+                **kind == LineKind::Content && line.trim_start_matches([' ', '\t']).starts_with('<')
+            })
+            .map(|(line, _)| &line[..leading(line)]),
+    );
+
+    // The new indentation of each line, or `None` where it is kept.
+    let mut indents: Vec<Option<String>> = vec![None; lines.len()];
+    let mut irregular: Option<Irregular> = None;
+
+    for (i, (line, kind)) in lines.iter().zip(kinds.iter()).enumerate() {
+        let indent = &line[..leading(line)];
+        if indent.len() == line.trim_end_matches('\r').len() {
+            // Blank or whitespace-only lines are left as they are.
+            continue;
+        }
+
+        indents[i] = match *kind {
+            LineKind::Protected => None,
+            LineKind::Content => {
+                let level = if indent.is_empty() {
+                    Some(0)
+                } else if !indent.contains(' ') {
+                    Some(indent.len())
+                } else if indent.contains('\t') {
+                    None
+                } else {
+                    step.filter(|step| indent.len() % step == 0)
+                        .map(|step| indent.len() / step)
+                };
+                match level {
+                    Some(level) => Some(unit.repeat(level)),
+                    None => {
+                        match irregular.as_mut() {
+                            Some(irregular) => irregular.count += 1,
+                            None => {
+                                irregular = Some(Irregular {
+                                    count: 1,
+                                    first_line: i + 1,
+                                })
+                            }
+                        }
+                        None
+                    }
+                }
+            }
+            LineKind::TagContinuation { tag_line } => {
+                let tag_indent = &lines[tag_line][..leading(lines[tag_line])];
+                match (&indents[tag_line], indent.strip_prefix(tag_indent)) {
+                    (Some(new_tag_indent), Some(alignment)) => {
+                        Some(format!("{}{}", new_tag_indent, alignment))
+                    }
+                    _ => None,
+                }
+            }
+        };
+    }
+
+    let mut output = String::with_capacity(source.len());
+    for (i, (line, indent)) in lines.iter().zip(indents).enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        match indent {
+            Some(indent) => {
+                output.push_str(&indent);
+                output.push_str(&line[leading(line)..]);
+            }
+            None => output.push_str(line),
+        }
+    }
+
+    (output, irregular)
+}
+
+/// The most common increase in indentation between consecutive lines indented with spaces, or
+/// `None` if no line is indented more than the one before it.
+fn infer_step<'a>(indents: impl Iterator<Item = &'a str>) -> Option<usize> {
+    let mut steps = HashMap::new();
+    let mut previous: Option<&str> = None;
+
+    for indent in indents {
+        if let Some(previous) = previous {
+            if !indent.contains('\t') && !previous.contains('\t') && indent.len() > previous.len() {
+                *steps.entry(indent.len() - previous.len()).or_insert(0) += 1;
+            }
+        }
+        previous = Some(indent);
+    }
+
+    // Ties go to the smaller step, which divides more lines.
+    steps
+        .into_iter()
+        .max_by(|(a_step, a_count), (b_step, b_count)| {
+            a_count.cmp(b_count).then(b_step.cmp(a_step))
+        })
+        .map(|(step, _)| step)
+}
+
+/// What the start of each line of `source` is inside of.
+fn line_kinds(source: &str) -> Vec<LineKind> {
+    let mut kinds = vec![LineKind::Content];
+    let mut state = State::Content;
+    // Whether each open element, innermost last, is under `xml:space="preserve"`.
+    let mut preserve = vec![];
+    let mut tag_start = 0;
+    let mut tag_line = 0;
+    let mut line = 0;
+
+    let mut chars = source.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+        let rest = &source[i..];
+        match state {
+            State::Content => {
+                if ch == '<' {
+                    (state, tag_start, tag_line) = if rest.starts_with("<!--") {
+                        (State::Comment, i, line)
+                    } else if rest.starts_with("<![CDATA[") {
+                        (State::CData, i, line)
+                    } else if rest.starts_with("<?") {
+                        (State::Pi, i, line)
+                    } else if rest.starts_with("<!") {
+                        (State::Doctype { brackets: 0 }, i, line)
+                    } else {
+                        (State::Tag { quote: None }, i, line)
+                    };
+                }
+            }
+            State::Tag { quote: Some(q) } if ch == q => state = State::Tag { quote: None },
+            State::Tag { quote: Some(_) } => {}
+            State::Tag { quote: None } => match ch {
+                '"' | '\'' => state = State::Tag { quote: Some(ch) },
+                '>' => {
+                    let tag = &source[tag_start..=i];
+                    if tag.starts_with("</") {
+                        preserve.pop();
+                    } else if !tag.ends_with("/>") {
+                        let inherited = preserve.last().copied().unwrap_or(false);
+                        preserve.push(match xml_space(tag) {
+                            Some(value) => value == "preserve",
+                            None => inherited,
+                        });
+                    }
+                    state = State::Content;
+                }
+                _ => {}
+            },
+            State::Comment if rest.starts_with("-->") => {
+                chars.nth(1);
+                state = State::Content;
+            }
+            State::CData if rest.starts_with("]]>") => {
+                chars.nth(1);
+                state = State::Content;
+            }
+            State::Pi if rest.starts_with("?>") => {
+                chars.next();
+                state = State::Content;
+            }
+            State::Doctype { brackets } => match ch {
+                '[' => {
+                    state = State::Doctype {
+                        brackets: brackets + 1,
+                    }
+                }
+                ']' => {
+                    state = State::Doctype {
+                        brackets: brackets.saturating_sub(1),
+                    }
+                }
+                '>' if brackets == 0 => state = State::Content,
+                _ => {}
+            },
+            State::Comment | State::CData | State::Pi => {}
+        }
+
+        if ch == '\n' {
+            line += 1;
+            kinds.push(match state {
+                State::Content if !preserve.last().copied().unwrap_or(false) => LineKind::Content,
+                State::Tag { quote: None } => LineKind::TagContinuation { tag_line },
+                _ => LineKind::Protected,
+            });
+        }
+    }
+
+    kinds
+}
+
+/// The value of the `xml:space` attribute in the start tag `tag`, if it has one.
+fn xml_space(tag: &str) -> Option<&str> {
+    let mut rest = tag;
+    while let Some(i) = rest.find("xml:space") {
+        let after = rest[i + "xml:space".len()..].trim_start();
+        let is_name_start = i == 0 || rest[..i].ends_with(char::is_whitespace);
+        if let (true, Some(after)) = (is_name_start, after.strip_prefix('=')) {
+            let after = after.trim_start();
+            let quote = after.chars().next()?;
+            if quote == '"' || quote == '\'' {
+                let value = &after[1..];
+                return value.find(quote).map(|end| &value[..end]);
+            }
+        }
+        rest = &rest[i + "xml:space".len()..];
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reindented(source: &str, unit: &str) -> String {
+        let (output, irregular) = reindent(source, unit);
+        assert!(irregular.is_none(), "{}", irregular.unwrap());
+        output
+    }
+
+    #[test]
+    fn changes_the_indentation_unit() {
+        let source = "<r>\n    <a>\n        <b/>\n    </a>\n</r>\n";
+        assert_eq!(
+            reindented(source, "  "),
+            "<r>\n  <a>\n    <b/>\n  </a>\n</r>\n"
+        );
+        assert_eq!(
+            reindented(source, "\t"),
+            "<r>\n\t<a>\n\t\t<b/>\n\t</a>\n</r>\n"
+        );
+    }
+
+    #[test]
+    fn tabs_count_one_level_each() {
+        assert_eq!(
+            reindented("<r>\n\t<a>\n\t\t<b/>\n\t</a>\n</r>", "   "),
+            "<r>\n   <a>\n      <b/>\n   </a>\n</r>"
+        );
+    }
+
+    #[test]
+    fn keeps_every_other_byte() {
+        let source = "<?xml version=\"1.0\"?>\n<r  a = 'x' >\n    <e   />\n    text  &amp;  more \n    <f x=\"1\"></f>   \n</r>";
+        let output = reindented(source, "\t");
+        assert_eq!(
+            output,
+            "<?xml version=\"1.0\"?>\n<r  a = 'x' >\n\t<e   />\n\ttext  &amp;  more \n\t<f x=\"1\"></f>   \n</r>"
+        );
+        let strip = |s: &str| {
+            s.split('\n')
+                .map(|line| line.trim_start_matches([' ', '\t']).to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(strip(&output), strip(source));
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        assert_eq!(
+            reindented("<r>\r\n    <a/>\r\n</r>\r\n", "  "),
+            "<r>\r\n  <a/>\r\n</r>\r\n"
+        );
+    }
+
+    #[test]
+    fn leaves_blank_lines_alone() {
+        assert_eq!(
+            reindented("<r>\n    <a/>\n      \n\n    <b/>\n</r>", "  "),
+            "<r>\n  <a/>\n      \n\n  <b/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn leaves_comments_alone() {
+        assert_eq!(
+            reindented(
+                "<r>\n    <!-- a\n         b -- c > d\n    -->\n    <a/>\n</r>",
+                "  "
+            ),
+            "<r>\n  <!-- a\n         b -- c > d\n    -->\n  <a/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn leaves_cdata_alone() {
+        assert_eq!(
+            reindented(
+                "<r>\n    <a><![CDATA[\n    <b>\n        ]]]\n]]></a>\n    <c/>\n</r>",
+                "  "
+            ),
+            "<r>\n  <a><![CDATA[\n    <b>\n        ]]]\n]]></a>\n  <c/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn leaves_processing_instructions_and_doctype_alone() {
+        assert_eq!(
+            reindented(
+                "<!DOCTYPE r [\n    <!ENTITY e \"]\">\n]>\n<r>\n    <?pi\n        data?>\n    <a/>\n</r>",
+                "  "
+            ),
+            "<!DOCTYPE r [\n    <!ENTITY e \"]\">\n]>\n<r>\n  <?pi\n        data?>\n  <a/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn leaves_preserved_elements_alone() {
+        let source = "<r>\n    <pre xml:space=\"preserve\">\n        <a>\n    keep</a>\n        <b xml:space=\"default\">\n            <c/>\n        </b>\n    </pre>\n    <d/>\n</r>";
+        assert_eq!(
+            reindented(source, "  "),
+            "<r>\n  <pre xml:space=\"preserve\">\n        <a>\n    keep</a>\n        <b xml:space=\"default\">\n      <c/>\n    </b>\n    </pre>\n  <d/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn reindents_mixed_content_by_its_indentation() {
+        assert_eq!(
+            reindented("<r>\n    <p>some <b>bold</b>\n        text</p>\n</r>", "  "),
+            "<r>\n  <p>some <b>bold</b>\n    text</p>\n</r>"
+        );
+    }
+
+    #[test]
+    fn moves_attribute_lines_with_their_tag() {
+        assert_eq!(
+            reindented(
+                "<r>\n    <a x=\"1\"\n       y=\"2\">\n        <b/>\n    </a>\n</r>",
+                "  "
+            ),
+            "<r>\n  <a x=\"1\"\n     y=\"2\">\n    <b/>\n  </a>\n</r>"
+        );
+    }
+
+    #[test]
+    fn leaves_attribute_values_alone() {
+        assert_eq!(
+            reindented("<r>\n    <a x=\"one\n        two\"/>\n</r>", "  "),
+            "<r>\n  <a x=\"one\n        two\"/>\n</r>"
+        );
+    }
+
+    #[test]
+    fn reports_irregular_lines_and_leaves_them() {
+        let source = "<r>\n    <a>\n        <b/>\n    </a>\n     <odd/>\n  \t<mixed/>\n    <c>\n        <d/>\n    </c>\n</r>";
+        let (output, irregular) = reindent(source, "  ");
+        assert_eq!(
+            output,
+            "<r>\n  <a>\n    <b/>\n  </a>\n     <odd/>\n  \t<mixed/>\n  <c>\n    <d/>\n  </c>\n</r>"
+        );
+        let irregular = irregular.unwrap();
+        assert_eq!((irregular.count, irregular.first_line), (2, 5));
+        assert_eq!(
+            irregular.to_string(),
+            "left 2 lines with irregular indentation as they are, the first at line 5"
+        );
+    }
+
+    #[test]
+    fn infers_the_most_common_step() {
+        assert_eq!(
+            infer_step(["", "  ", "    ", "  ", "      "].into_iter()),
+            Some(2)
+        );
+        assert_eq!(infer_step(["", "", ""].into_iter()), None);
+        assert_eq!(infer_step(["", "\t", "", "   "].into_iter()), Some(3));
+    }
+
+    #[test]
+    fn reads_xml_space() {
+        assert_eq!(xml_space("<a xml:space=\"preserve\">"), Some("preserve"));
+        assert_eq!(xml_space("<a xml:space = 'default'>"), Some("default"));
+        assert_eq!(xml_space("<a my-xml:space=\"preserve\">"), None);
+        assert_eq!(xml_space("<a>"), None);
+    }
+}
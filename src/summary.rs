@@ -0,0 +1,92 @@
+use std::fmt::Write;
+
+use crate::{
+    audit::{self, json_string, json_strings},
+    timings::{self, Timings},
+};
+
+/// The version of the `--summary-json` format, raised whenever a field changes meaning or is
+/// removed. Fields may be added without raising it.
+pub const VERSION: u32 = 2;
+
+/// The `--summary-json` report of a run: the number of documents by outcome, then one entry per
+/// document. Unlike `--log-file`, it is written once, when the run is over.
+#[derive(Debug, Default)]
+pub struct Summary {
+    files: Vec<File>,
+}
+
+#[derive(Debug)]
+struct File {
+    path: Option<String>,
+    action: &'static str,
+    error: Option<String>,
+    categories: Vec<&'static str>,
+    location: Option<(usize, usize)>,
+    timings: Option<String>,
+}
+
+impl Summary {
+    pub fn add(&mut self, entry: &audit::Entry) {
+        self.files.push(File {
+            path: entry.path.map(str::to_string),
+            action: entry.action,
+            error: entry.error.clone(),
+            categories: entry.categories.to_vec(),
+            location: entry.location,
+            timings: entry.timings.clone(),
+        });
+    }
+
+    /// The report, with `timings` the aggregate for the run.
+    pub fn to_json(&self, timings: &Timings) -> String {
+        let count = |actions: &[&str]| {
+            self.files
+                .iter()
+                .filter(|file| actions.contains(&file.action))
+                .count()
+        };
+
+        let mut json = format!(
+            "{{\"version\":{},\"tool\":\"xml-pretty\",\"total\":{},\"changed\":{},\"unchanged\":{},\"printed\":{},\"errored\":{},\"skipped\":{},\"timings\":{},\"peak_rss_kib\":{},\"files\":[",
+            VERSION,
+            self.files.len(),
+            count(&["rewritten", "unformatted"]),
+            count(&["unchanged", "checked"]),
+            count(&["printed"]),
+            count(&["error"]),
+            count(&["skipped"]),
+            timings.to_json(),
+            timings::peak_rss_kib().map_or("null".to_string(), |kib| kib.to_string())
+        );
+
+        for (i, file) in self.files.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"path\":{},\"status\":{}",
+                file.path.as_deref().map_or("null".to_string(), json_string),
+                json_string(file.action)
+            );
+            if !file.categories.is_empty() {
+                let _ = write!(json, ",\"categories\":{}", json_strings(&file.categories));
+            }
+            if let Some(message) = file.error.as_deref() {
+                let _ = write!(json, ",\"error\":{{\"message\":{}", json_string(message));
+                if let Some((line, column)) = file.location {
+                    let _ = write!(json, ",\"line\":{},\"column\":{}", line, column);
+                }
+                json.push('}');
+            }
+            if let Some(timings) = file.timings.as_deref() {
+                let _ = write!(json, ",\"timings\":{}", timings);
+            }
+            json.push('}');
+        }
+
+        json.push_str("]}\n");
+        json
+    }
+}
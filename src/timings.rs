@@ -0,0 +1,112 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Wall-clock time spent in each phase of a run, as reported by `--timings`.
+#[derive(Debug)]
+pub struct Timings {
+    started: Instant,
+    pub read: Duration,
+    pub parse: Duration,
+    pub serialize: Duration,
+    pub compare: Duration,
+    pub write: Duration,
+}
+
+impl Timings {
+    pub fn start() -> Self {
+        Timings {
+            started: Instant::now(),
+            read: Duration::ZERO,
+            parse: Duration::ZERO,
+            serialize: Duration::ZERO,
+            compare: Duration::ZERO,
+            write: Duration::ZERO,
+        }
+    }
+
+    /// Runs `f`, adding the time it took to the phase selected by `phase`.
+    pub fn measure<T>(
+        &mut self,
+        phase: fn(&mut Self) -> &mut Duration,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        let start = Instant::now();
+        let value = f();
+        *phase(self) += start.elapsed();
+        value
+    }
+
+    /// Adds the phases measured by `other`, e.g. to aggregate the timings of many documents.
+    pub fn add(&mut self, other: &Timings) {
+        self.read += other.read;
+        self.parse += other.parse;
+        self.serialize += other.serialize;
+        self.compare += other.compare;
+        self.write += other.write;
+    }
+
+    /// Wall-clock time since the run started, including work outside the measured phases.
+    pub fn total(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    fn phases(&self) -> [(&'static str, Duration); 6] {
+        [
+            ("read", self.read),
+            ("parse", self.parse),
+            ("serialize", self.serialize),
+            ("compare", self.compare),
+            ("write", self.write),
+            ("total", self.total()),
+        ]
+    }
+
+    /// The phases as a JSON object of milliseconds, e.g. `{"read_ms":0.125,...,"total_ms":2.5}`,
+    /// for `--log-file` and `--summary-json`.
+    pub fn to_json(&self) -> String {
+        let phases = self
+            .phases()
+            .iter()
+            .map(|(name, duration)| {
+                format!("\"{}_ms\":{:.3}", name, duration.as_secs_f64() * 1000.0)
+            })
+            .collect::<Vec<_>>();
+        format!("{{{}}}", phases.join(","))
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, duration) in self.phases() {
+            writeln!(
+                f,
+                "{:<10} {:>10.3}ms",
+                name,
+                duration.as_secs_f64() * 1000.0
+            )?;
+        }
+
+        if let Some(kib) = peak_rss_kib() {
+            writeln!(f, "{:<10} {:>10}kB", "peak rss", kib)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Peak resident set size of the current process, where the platform makes it cheap to ask.
+#[cfg(target_os = "linux")]
+pub fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn peak_rss_kib() -> Option<u64> {
+    None
+}
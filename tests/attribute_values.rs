@@ -0,0 +1,45 @@
+//! Attribute values come through formatting and wrapping with their meaning intact.
+
+mod common;
+
+use common::format;
+
+#[test]
+fn whitespace_references_round_trip() {
+    let once = format(
+        &[],
+        "<e multi=\"line1&#10;line2&#9;tab&#13;cr\" raw=\"a\tb\"/>",
+    )
+    .unwrap();
+    assert_eq!(
+        once,
+        "<e multi=\"line1&#x000A;line2&#x0009;tab&#x000D;cr\" raw=\"a&#x0009;b\" />\n"
+    );
+    assert_eq!(format(&[], &once).unwrap(), once);
+}
+
+#[test]
+fn empty_and_whitespace_values_wrap_like_any_other() {
+    assert_eq!(
+        format(
+            &["-l", "30"],
+            "<input checked=\"\" label=\" \" name=\"aaaaaaaaaaaaaa\"/>"
+        )
+        .unwrap(),
+        "<input checked=\"\"\n  label=\" \"\n  name=\"aaaaaaaaaaaaaa\" />\n"
+    );
+}
+
+#[test]
+fn multi_line_values_take_one_line_when_wrapping() {
+    let wrapped = format(
+        &["-l", "30"],
+        "<e first=\"aaaaaaaaaaaaaaa\" multi=\"line1\nline2\" last=\"zzzzzzzzzzzzzzzzzzz\"/>",
+    )
+    .unwrap();
+    assert_eq!(
+        wrapped,
+        "<e first=\"aaaaaaaaaaaaaaa\"\n  multi=\"line1&#x000A;line2\"\n  last=\"zzzzzzzzzzzzzzzzzzz\" />\n"
+    );
+    assert_eq!(format(&["-l", "30"], &wrapped).unwrap(), wrapped);
+}
@@ -0,0 +1,28 @@
+//! Whitespace-only text between block children is a separator, never a blank line.
+
+mod common;
+
+use common::{format, run_with_stdin, stderr, stdout, xml_pretty, TempDir};
+
+const DOCUMENT: &str = "<r>\n  <a/>\n\n\n\n  <b>\n\n    <c/>\n\n\n    <!-- x -->\n\n    <![CDATA[y]]>\n\n  </b>\n\n</r>\n";
+
+const FORMATTED: &str =
+    "<r>\n  <a/>\n  <b>\n    <c/>\n    <!-- x -->\n    <![CDATA[y]]>\n  </b>\n</r>\n";
+
+#[test]
+fn gaps_between_children_are_dropped() {
+    assert_eq!(format(&[], DOCUMENT).unwrap(), FORMATTED);
+
+    let output = run_with_stdin(xml_pretty().args(["check", "--stdin"]), FORMATTED);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn documents_on_stdout_are_not_followed_by_a_blank_line() {
+    let dir = TempDir::new("blank-lines");
+    let first = dir.write("first.xml", DOCUMENT);
+    let second = dir.write("second.xml", "<s/>");
+    let output = xml_pretty().arg(&first).arg(&second).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}<s/>\n", FORMATTED));
+}
@@ -0,0 +1,52 @@
+//! Output piped into a reader that goes away early, as with `xml-pretty big.xml | head`, ends
+//! the run quietly and successfully.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    io::Read,
+    process::{Command, Stdio},
+};
+
+use common::{stderr, xml_pretty, TempDir};
+
+/// Runs `command`, reading one byte of its output and then closing the pipe. The output is
+/// far larger than a pipe holds, so the command is still writing when it is closed.
+fn run_closing_early(command: &mut Command) -> std::process::Output {
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdout = child.stdout.take().unwrap();
+    let mut byte = [0];
+    stdout.read_exact(&mut byte).unwrap();
+    drop(stdout);
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn closed_stdout_exits_quietly() {
+    let dir = TempDir::new("broken-pipe");
+    let document = format!("<r>{}</r>", "<item a=\"1\">text</item>".repeat(10_000));
+    let path = dir.write("big.xml", document);
+
+    let output = run_closing_early(xml_pretty().arg(&path));
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stderr(&output), "");
+
+    // The same through a shell pipeline, with `head` as the reader that goes away. Anything
+    // xml-pretty writes to stderr comes before the status the subshell reports.
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg("(\"$0\" \"$1\"; echo \"status $?\" >&2) | head -c1 >/dev/null")
+        .arg(env!("CARGO_BIN_EXE_xml-pretty"))
+        .arg(&path)
+        .env("RUST_BACKTRACE", "0")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(stderr(&output), "status 0\n");
+}
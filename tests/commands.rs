@@ -0,0 +1,125 @@
+//! The subcommands' help, and invocations without a command, which are aliases for one.
+
+mod common;
+
+use common::{stderr, stdout, xml_pretty, TempDir};
+
+/// The help of `command`, without the usage line, which names the binary.
+fn help(command: &str) -> String {
+    let output = xml_pretty().args([command, "-h"]).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let help = stderr(&output);
+    let (usage, rest) = help.split_once('\n').unwrap();
+    assert!(
+        usage.ends_with(&format!(" {} [OPTIONS]", command)),
+        "{}",
+        usage
+    );
+    rest.to_string()
+}
+
+/// The short and long names of the options listed in `help`.
+fn options(help: &str) -> Vec<&str> {
+    help.lines()
+        .flat_map(|line| {
+            line.split_whitespace()
+                .take_while(|word| word.starts_with('-'))
+                .map(|option| option.trim_end_matches(','))
+        })
+        .collect()
+}
+
+#[test]
+fn each_command_has_its_own_help() {
+    let cases: &[(&str, &str, &[&str], &[&str])] = &[
+        (
+            "format",
+            "\nFormats XML documents",
+            &["-o", "--replace", "--snapshot-dir", "--accept", "--lint"],
+            &["--depth"],
+        ),
+        (
+            "check",
+            "\nChecks that XML documents are already formatted, without changing them.",
+            &[
+                "--check-sorted-attributes",
+                "--diff-tool",
+                "--sort-attributes",
+            ],
+            &["-o", "--replace", "--snapshot-dir", "--accept", "--lint"],
+        ),
+        (
+            "minify",
+            "\nWrites XML documents without indentation",
+            &["-o", "--replace"],
+            &["--indent", "--lint"],
+        ),
+        (
+            "tree",
+            "\nPrints the path of each element",
+            &["--depth", "--attrs"],
+            &["-o", "--outline", "--outline-depth", "--outline-attrs"],
+        ),
+        (
+            "diff",
+            "\nReports whether two XML documents are equivalent",
+            &["-h"],
+            &["-o", "--replace"],
+        ),
+    ];
+
+    for (command, description, listed, unlisted) in cases {
+        let help = help(command);
+        assert!(help.starts_with(description), "{}:\n{}", command, help);
+        let options = options(&help);
+        for option in *listed {
+            assert!(options.contains(option), "{} lacks {}", command, option);
+        }
+        for option in *unlisted {
+            assert!(!options.contains(option), "{} lists {}", command, option);
+        }
+    }
+    assert!(!help("check").contains("Formats XML"));
+}
+
+#[test]
+fn top_level_help_lists_the_commands() {
+    let output = xml_pretty().arg("-h").output().unwrap();
+    assert!(output.status.success());
+    let help = stderr(&output);
+    for command in ["format", "check", "minify", "diff", "tree"] {
+        assert!(
+            help.lines()
+                .any(|line| line.trim_start().starts_with(&format!("{} ", command))),
+            "{}",
+            help
+        );
+    }
+}
+
+#[test]
+fn invocations_without_a_command_are_aliases() {
+    let dir = TempDir::new("commands");
+    let document = dir.write("doc.xml", "<r a=\"x\ty\"><s><t/></s></r>");
+    let run = |args: &[&str]| {
+        let output = xml_pretty().args(args).arg(&document).output().unwrap();
+        assert!(output.status.success(), "{:?}: {}", args, stderr(&output));
+        stdout(&output)
+    };
+
+    assert_eq!(run(&[]), run(&["format"]));
+    assert_eq!(run(&["--indent", "4"]), run(&["format", "--indent", "4"]));
+
+    // The options `tree` replaced.
+    let tree = run(&["tree", "--depth", "2", "--attrs"]);
+    assert_eq!(tree, "/r a=\"x\\ty\"\n/r/s\n");
+    assert_eq!(
+        run(&["--outline", "--outline-depth", "2", "--outline-attrs"]),
+        tree
+    );
+    assert_eq!(
+        run(&["--outline", "--outline-depth=2", "--outline-attrs"]),
+        tree
+    );
+    assert_eq!(run(&["--outline"]), run(&["tree"]));
+}
@@ -0,0 +1,46 @@
+//! Comment bodies are written exactly as they were parsed; only the indentation before the
+//! comment changes.
+
+mod common;
+
+use common::format;
+
+#[test]
+fn interior_whitespace_is_kept_byte_for_byte() {
+    let table = "\n  name     value   note\n  a        1       x  \n\tb\t\t2\n";
+    let formatted = format(
+        &[],
+        format!("<r>\n<s>\n<!--{}-->\n</s><!--   padded   --></r>", table),
+    )
+    .unwrap();
+    assert_eq!(
+        formatted,
+        format!(
+            "<r>\n  <s>\n    <!--{}-->\n  </s>\n  <!--   padded   -->\n</r>\n",
+            table
+        )
+    );
+    assert_eq!(format(&[], &formatted).unwrap(), formatted);
+}
+
+#[test]
+fn comments_are_never_escaped() {
+    let comment = "<!-- a & b é &amp; < -->";
+    let document = format!("<r>{}<s>é &amp;</s></r>", comment);
+    let cases: &[(&[&str], &str)] = &[
+        (&[], "é &amp;"),
+        (&["-H"], "é &#x0026;"),
+        (&["--compat", "xmllint"], "é &amp;"),
+    ];
+    for (args, text) in cases {
+        let formatted = format(args, &document).unwrap();
+        assert!(
+            formatted.contains(&format!("\n  {}\n", comment)),
+            "{:?}: {}",
+            args,
+            formatted
+        );
+        // The text beside it is escaped as the mode says.
+        assert!(formatted.contains(text), "{:?}: {}", args, formatted);
+    }
+}
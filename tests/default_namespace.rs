@@ -0,0 +1,93 @@
+//! `--check-default-namespace` and `--fix-default-namespace`, for elements that an `xmlns=""`
+//! takes out of the root's default namespace.
+
+mod common;
+
+use common::{format, run_with_stdin, stderr, xml_pretty, TempDir};
+
+const SVG: &str = "http://www.w3.org/2000/svg";
+
+/// A pasted group reset to no namespace, with nested content inheriting the reset, and an
+/// `xmlns=""` inside XHTML content, which is not the SVG namespace's to judge.
+const DOCUMENT: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\">\
+                        <g xmlns=\"\"><rect/><g><path/></g></g>\
+                        <circle/>\
+                        <foreignObject><div xmlns=\"http://www.w3.org/1999/xhtml\"><p xmlns=\"\"/></div></foreignObject>\
+                        </svg>";
+
+const FIXED: &str = "<svg xmlns=\"http://www.w3.org/2000/svg\">\n  \
+                     <g>\n    <rect/>\n    <g>\n      <path/>\n    </g>\n  </g>\n  \
+                     <circle/>\n  \
+                     <foreignObject>\n    \
+                     <div xmlns=\"http://www.w3.org/1999/xhtml\">\n      <p xmlns=\"\" />\n    </div>\n  \
+                     </foreignObject>\n\
+                     </svg>\n";
+
+#[test]
+fn elements_taken_out_of_the_namespace_are_reported() {
+    let dir = TempDir::new("default-namespace");
+    let document = dir.write("doc.xml", DOCUMENT);
+    let output = xml_pretty()
+        .args(["check", "--check-default-namespace", SVG])
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let message = stderr(&output);
+    let findings = message
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        [
+            "  1:41: element /svg/g is in no namespace, not 'http://www.w3.org/2000/svg'",
+            "  1:53: element /svg/g/rect is in no namespace, not 'http://www.w3.org/2000/svg'",
+            "  1:60: element /svg/g/g is in no namespace, not 'http://www.w3.org/2000/svg'",
+            "  1:63: element /svg/g/g/path is in no namespace, not 'http://www.w3.org/2000/svg'",
+        ],
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn fix_removes_the_resets_and_the_result_passes_the_lint() {
+    let fixed = format(
+        &["--check-default-namespace", SVG, "--fix-default-namespace"],
+        DOCUMENT,
+    )
+    .unwrap();
+    assert_eq!(fixed, FIXED);
+
+    let output = run_with_stdin(
+        xml_pretty().args(["check", "--check-default-namespace", SVG, "--stdin"]),
+        &fixed,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // A root in some other default namespace is left alone.
+    let fixed = format(
+        &[
+            "--check-default-namespace",
+            "urn:other",
+            "--fix-default-namespace",
+        ],
+        DOCUMENT,
+    )
+    .unwrap();
+    assert!(fixed.contains("\n  <g xmlns=\"\">\n"), "{}", fixed);
+}
+
+#[test]
+fn fix_needs_the_namespace() {
+    let output = xml_pretty()
+        .args(["--fix-default-namespace", "--stdin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stderr(&output),
+        "ERROR: --fix-default-namespace needs the namespace from --check-default-namespace.\n"
+    );
+}
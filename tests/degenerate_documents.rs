@@ -0,0 +1,116 @@
+//! Documents with nothing but a prolog and an empty root, which should format to a canonical
+//! form that passes the lint straight away, however they are written out.
+
+mod common;
+
+use std::fs;
+
+use common::{format, stderr, xml_pretty, TempDir};
+
+const DECLARATION: &str = "<?xml version=\"1.0\"?>";
+const STANDALONE: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>";
+
+/// Each input with the options it is formatted with and its canonical output.
+fn cases() -> Vec<(String, &'static [&'static str], String)> {
+    let mut cases = vec![];
+    for (root, padded, unpadded) in [
+        ("<resources/>", "<resources/>", "<resources/>"),
+        ("<resources></resources>", "<resources/>", "<resources/>"),
+        (
+            "<resources a=\"1\" b='2'/>",
+            "<resources a=\"1\" b=\"2\" />",
+            "<resources a=\"1\" b=\"2\"/>",
+        ),
+        (
+            "<resources xmlns=\"urn:r\"/>",
+            "<resources xmlns=\"urn:r\" />",
+            "<resources xmlns=\"urn:r\"/>",
+        ),
+    ] {
+        cases.push((root.to_string(), &[][..], format!("{}\n", padded)));
+        cases.push((
+            root.to_string(),
+            &["--end-pad", "0"][..],
+            format!("{}\n", unpadded),
+        ));
+
+        for declaration in [DECLARATION, STANDALONE] {
+            for separator in ["", "\n", "\n\n"] {
+                let input = format!("{}{}{}\n", declaration, separator, root);
+                cases.push((
+                    input.clone(),
+                    &[][..],
+                    format!("{}\n\n{}\n", declaration, padded),
+                ));
+                cases.push((
+                    input.clone(),
+                    &["--end-pad", "0"][..],
+                    format!("{}\n\n{}\n", declaration, unpadded),
+                ));
+                cases.push((
+                    input,
+                    &["--prolog-blank-line", "no"][..],
+                    format!("{}\n{}\n", declaration, padded),
+                ));
+            }
+        }
+
+        cases.push((
+            format!("{}{}", STANDALONE, root),
+            &["--omit-utf8-encoding"][..],
+            format!("<?xml version=\"1.0\" standalone=\"yes\"?>\n\n{}\n", padded),
+        ));
+    }
+    cases
+}
+
+#[test]
+fn prolog_and_empty_root_format_to_the_canonical_form() {
+    for (input, args, expected) in cases() {
+        assert_eq!(
+            format(args, &input).as_deref(),
+            Ok(expected.as_str()),
+            "{:?} {:?}",
+            args,
+            input
+        );
+    }
+}
+
+#[test]
+fn canonical_form_is_written_the_same_everywhere_and_passes_the_lint() {
+    let dir = TempDir::new("degenerate-documents");
+    for (i, (input, args, expected)) in cases().into_iter().enumerate() {
+        let source = dir.write(&format!("{}.xml", i), &input);
+
+        let written = dir.path(&format!("{}.out.xml", i));
+        let output = xml_pretty()
+            .args(args)
+            .arg("-o")
+            .arg(&written)
+            .arg(&source)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(
+            fs::read_to_string(&written).unwrap(),
+            expected,
+            "{:?}",
+            input
+        );
+
+        let output = xml_pretty()
+            .arg("check")
+            .args(args)
+            .arg(&written)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{:?} {:?}: {}",
+            args,
+            input,
+            stderr(&output)
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! The `diff` command: its exit codes, and what counts as a difference.
+
+mod common;
+
+use common::{stderr, stdout, xml_pretty, TempDir};
+
+#[test]
+fn exit_code_tells_equal_from_different_from_unreadable() {
+    let dir = TempDir::new("diff");
+    let original = dir.write("original.xml", "<r a=\"1\"><s>t</s></r>");
+    let same = dir.write("same.xml", "<r a=\"1\"><s>t</s></r>");
+    let changed = dir.write("changed.xml", "<r a=\"2\"><s>t</s></r>");
+    let broken = dir.write("broken.xml", "<r><s></r>");
+
+    let diff = |left: &_, right: &_| {
+        xml_pretty()
+            .arg("diff")
+            .arg(left)
+            .arg(right)
+            .output()
+            .unwrap()
+    };
+
+    let output = diff(&original, &same);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+
+    let output = diff(&original, &changed);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "documents differ at /r/@a\n  left:  \"1\"\n  right: \"2\"\n"
+    );
+
+    for (left, right) in [(&original, &broken), (&broken, &original)] {
+        let output = diff(left, right);
+        assert_eq!(output.status.code(), Some(2));
+        assert_eq!(stdout(&output), "");
+        assert!(
+            stderr(&output).starts_with(&format!("Error: Failed to parse '{}'", broken.display())),
+            "{}",
+            stderr(&output)
+        );
+    }
+
+    let output = xml_pretty().arg("diff").arg(&original).output().unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stderr(&output),
+        "ERROR: diff takes exactly two XML documents.\nRun with -h for usage information.\n"
+    );
+}
+
+#[test]
+fn insignificant_whitespace_attribute_order_and_quotes_are_ignored() {
+    let dir = TempDir::new("diff-whitespace");
+    let compact = dir.write("compact.xml", "<r a=\"x y\" b='1'><s>text</s><t/></r>");
+    let formatted = dir.write(
+        "formatted.xml",
+        "<r b=\"1\"  a=\"x y\">\n  <s>\n    text\n  </s>\n\n  <t />\n</r>\n",
+    );
+    let output = xml_pretty()
+        .arg("diff")
+        .arg(&compact)
+        .arg(&formatted)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0), "{}", stdout(&output));
+
+    // Whitespace inside attribute values is significant.
+    let spaced = dir.write("spaced.xml", "<r a=\"x  y\" b=\"1\"><s>text</s><t/></r>");
+    let output = xml_pretty()
+        .arg("diff")
+        .arg(&compact)
+        .arg(&spaced)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout(&output).starts_with("documents differ at /r/@a\n"),
+        "{}",
+        stdout(&output)
+    );
+}
@@ -0,0 +1,57 @@
+//! `--diff-tool` runs a command on the original and formatted document of each failing check,
+//! and removes their temporary files however it ends.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{stderr, stdout, xml_pretty, TempDir};
+
+#[test]
+fn temporary_files_are_removed_when_the_tool_fails() {
+    let dir = TempDir::new("diff-tool");
+    let document = dir.write("doc.xml", "<r><a/></r>");
+
+    // The quoted script is one argument, as in a shell.
+    let output = xml_pretty()
+        .args([
+            "check",
+            "--diff-tool",
+            r#"sh -c 'cat "$0" "$1"; echo; echo "$0"; echo "$1"; exit 3' {old} {new}"#,
+        ])
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+
+    let printed = stdout(&output);
+    let lines = printed.lines().collect::<Vec<_>>();
+    assert_eq!(lines[..4], ["<r><a/></r><r>", "  <a/>", "</r>", ""]);
+    let (old, new) = (Path::new(lines[4]), Path::new(lines[5]));
+    assert!(old.to_string_lossy().ends_with(".old.xml"), "{}", printed);
+    assert!(new.to_string_lossy().ends_with(".new.xml"), "{}", printed);
+    assert!(!old.exists() && !new.exists());
+
+    assert!(
+        stderr(&output).contains("diff tool 'sh' exit status: 3"),
+        "{}",
+        stderr(&output)
+    );
+    // The document itself is left alone.
+    assert_eq!(fs::read_to_string(&document).unwrap(), "<r><a/></r>");
+}
+
+#[test]
+fn an_unterminated_quote_is_a_usage_error() {
+    let output = xml_pretty()
+        .args(["check", "--diff-tool", "diff 'x", "doc.xml"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stderr(&output),
+        "ERROR: cannot split the --diff-tool command: missing closing quote.\n"
+    );
+}
@@ -0,0 +1,42 @@
+//! `--dump-tree`, which prints the parsed form of a document instead of formatting it.
+
+mod common;
+
+use common::{run_with_stdin, stderr, stdout, xml_pretty, TempDir};
+
+const DOCUMENT: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                        <!DOCTYPE r>\n\
+                        <r x=\"a \t\">\n  \
+                        <!-- note -->\n  \
+                        text \"quoted\"\n  \
+                        <![CDATA[<raw>]]>\n  \
+                        <p:s xmlns:p=\"urn:p\" p:y=\"1\"/>\n\
+                        </r>\n";
+
+/// The parser drops the whitespace-only text between nodes, but keeps the indentation around
+/// the text with words in it.
+const DUMP: &str = "Declaration version=\"1.0\" encoding=\"UTF-8\"\n\
+                    DocumentType \"r\"\n\
+                    Element <r> x=\"a·→\"\n  \
+                    Comment \"·note·\"\n  \
+                    Text \"↵··text·\\\"quoted\\\"↵··\"\n  \
+                    CDataSection \"<raw>\"\n  \
+                    Element <p:s> xmlns:p=\"urn:p\" p:y=\"1\"\n";
+
+#[test]
+fn each_node_is_listed_with_its_whitespace_visible() {
+    let dir = TempDir::new("dump-tree");
+    let document = dir.write("doc.xml", DOCUMENT);
+
+    let output = xml_pretty()
+        .arg("--dump-tree")
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), DUMP);
+
+    let output = run_with_stdin(xml_pretty().args(["--dump-tree", "--stdin"]), DOCUMENT);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), DUMP);
+}
@@ -0,0 +1,55 @@
+//! Empty documents are errors unless `--allow-empty` is given, in which case they give empty
+//! output.
+
+mod common;
+
+use common::{format, run_with_stdin, stderr, stdout, xml_pretty, TempDir};
+
+#[test]
+fn empty_stdin_is_an_error_without_allow_empty() {
+    for input in ["", " \n\t\n"] {
+        let error = format(&[], input).unwrap_err();
+        assert_eq!(
+            error,
+            "Error: Failed to prettify from stdin\n\n\
+             Caused by:\n    the document is empty (use --allow-empty to accept it)\n"
+        );
+    }
+}
+
+#[test]
+fn empty_stdin_gives_empty_output_with_allow_empty() {
+    for input in ["", " \n\t\n"] {
+        assert_eq!(format(&["--allow-empty"], input).unwrap(), "");
+
+        let output = run_with_stdin(
+            xml_pretty().args(["check", "--allow-empty", "--stdin"]),
+            input,
+        );
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+}
+
+#[test]
+fn empty_files_are_skipped_with_allow_empty() {
+    let dir = TempDir::new("empty-input");
+    let empty = dir.write("empty.xml", "");
+    let document = dir.write("doc.xml", "<r><a/></r>");
+
+    let output = xml_pretty().arg(&empty).arg(&document).output().unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("the document is empty (use --allow-empty to accept it)"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = xml_pretty()
+        .arg("--allow-empty")
+        .arg(&empty)
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "<r>\n  <a/>\n</r>\n");
+}
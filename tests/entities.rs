@@ -0,0 +1,28 @@
+//! Character and entity references are decoded on parsing and re-escaped only as the entity
+//! mode requires, so a second pass changes nothing.
+
+mod common;
+
+use common::format;
+
+const DOCUMENT: &str =
+    "<r a=\"&#x41;&#xA0;&amp;&#38;&lt;&quot;\">&#x41;&#xA0;&amp;&#38;&lt;&gt;</r>";
+
+#[test]
+fn references_are_normalized_once() {
+    let cases: &[(&[&str], &str)] = &[
+        (
+            &[],
+            "<r a=\"A&#x00A0;&amp;&amp;&lt;&quot;\">\n  A&#x00A0;&amp;&amp;&lt;&gt;\n</r>\n",
+        ),
+        (
+            &["-H"],
+            "<r a=\"A&#x00A0;&#x0026;&#x0026;&#x003C;&#x0022;\">\n  A&#x00A0;&#x0026;&#x0026;&#x003C;&#x003E;\n</r>\n",
+        ),
+    ];
+    for (args, expected) in cases {
+        let once = format(args, DOCUMENT).unwrap();
+        assert_eq!(once, *expected, "{:?}", args);
+        assert_eq!(format(args, &once).unwrap(), once, "{:?}", args);
+    }
+}
@@ -0,0 +1,85 @@
+//! `--input-fd` and `--output-fd`: documents read from and written to descriptors the caller
+//! already has open, here the ends of `pipe()` pairs.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{
+    io::{self, Read, Write},
+    os::{fd::AsRawFd, unix::process::CommandExt},
+    process::Command,
+};
+
+use common::{stderr, stdout, xml_pretty};
+
+/// Keeps `fds` open in the child. The standard library opens pipes close-on-exec, so any
+/// descriptor not passed here is closed by the time xml-pretty runs.
+fn passing_fds(command: &mut Command, fds: Vec<i32>) -> &mut Command {
+    // SAFETY: only fcntl, which is async-signal-safe, runs between fork and exec.
+    unsafe {
+        command.pre_exec(move || {
+            for &fd in &fds {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[test]
+fn document_is_read_from_one_pipe_and_written_to_another() {
+    let (input_reader, mut input_writer) = io::pipe().unwrap();
+    let (mut output_reader, output_writer) = io::pipe().unwrap();
+    input_writer.write_all(b"<r><a>1</a></r>").unwrap();
+    drop(input_writer);
+
+    let mut command = xml_pretty();
+    command
+        .arg("--input-fd")
+        .arg(input_reader.as_raw_fd().to_string())
+        .arg("--output-fd")
+        .arg(output_writer.as_raw_fd().to_string());
+    passing_fds(
+        &mut command,
+        vec![input_reader.as_raw_fd(), output_writer.as_raw_fd()],
+    );
+    let child = command.spawn().unwrap();
+    // Only the child holds the write end now, so reading ends when it exits.
+    drop(input_reader);
+    drop(output_writer);
+
+    let mut formatted = String::new();
+    output_reader.read_to_string(&mut formatted).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(formatted, "<r>\n  <a>\n    1\n  </a>\n</r>\n");
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn descriptors_that_are_not_open_are_usage_errors() {
+    // Open here, but not passed on, so closed in the child.
+    let (reader, writer) = io::pipe().unwrap();
+    let reader = reader.as_raw_fd().to_string();
+    let writer = writer.as_raw_fd().to_string();
+
+    for (flag, fd) in [
+        ("--input-fd", "-1"),
+        ("--input-fd", reader.as_str()),
+        ("--output-fd", "-1"),
+        ("--output-fd", writer.as_str()),
+    ] {
+        let output = xml_pretty()
+            .arg(format!("{}={}", flag, fd))
+            .output()
+            .unwrap();
+        assert_eq!(output.status.code(), Some(2), "{} {}", flag, fd);
+        assert_eq!(
+            stderr(&output),
+            format!("ERROR: {} {} is not an open file descriptor.\n", flag, fd)
+        );
+    }
+}
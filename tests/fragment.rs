@@ -0,0 +1,44 @@
+//! Content after the root element is an error, unless `--fragment` allows several top-level
+//! elements.
+
+mod common;
+
+use common::{format, stderr, xml_pretty, TempDir};
+
+const TWO_ROOTS: &str = "<?xml version=\"1.0\"?>\n<a x=\"1\"><b/></a>\n<c>text</c>\n";
+
+#[test]
+fn content_after_the_root_element_is_an_error() {
+    let error = format(&[], TWO_ROOTS).unwrap_err();
+    assert!(
+        error.contains("found content after root element at line 3, column 1"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn fragment_allows_several_top_level_elements() {
+    let formatted = format(&["--fragment"], TWO_ROOTS).unwrap();
+    assert_eq!(
+        formatted,
+        "<?xml version=\"1.0\"?>\n\n<a x=\"1\">\n  <b/>\n</a>\n<c>\n  text\n</c>\n"
+    );
+
+    // Top-level text and comments are kept, and the output passes the lint.
+    let formatted = format(
+        &["--fragment", "--indent", "4"],
+        "<!-- c --><a><b/></a>text",
+    )
+    .unwrap();
+    assert_eq!(formatted, "<!-- c -->\n<a>\n    <b/>\n</a>\ntext\n");
+
+    let dir = TempDir::new("fragment");
+    let document = dir.write("fragment.xml", &formatted);
+    let output = xml_pretty()
+        .args(["check", "--fragment", "--indent", "4"])
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+}
@@ -0,0 +1,81 @@
+//! Gzip-compressed documents are decompressed to be formatted or checked, and `--replace`
+//! writes them back compressed.
+
+mod common;
+
+use std::{fs, io::Read};
+
+use common::{run_with_stdin, stderr, stdout, xml_pretty, TempDir};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+const UNFORMATTED: &str = "<r><a>1</a></r>";
+const FORMATTED: &str = "<r>\n  <a>\n    1\n  </a>\n</r>\n";
+
+fn compress(s: &str) -> Vec<u8> {
+    use std::io::Write;
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(s.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn decompress(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    GzDecoder::new(bytes).read_to_string(&mut output).unwrap();
+    output
+}
+
+#[test]
+fn gzipped_document_is_formatted_uncompressed_to_stdout() {
+    let dir = TempDir::new("gzip-format");
+    // Found by its content, whatever the name.
+    let document = dir.write("doc.xml", compress(UNFORMATTED));
+
+    let output = xml_pretty().arg(&document).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), FORMATTED);
+
+    let output = run_with_stdin(xml_pretty().arg("--stdin"), compress(UNFORMATTED));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), FORMATTED);
+}
+
+#[test]
+fn replace_writes_a_gzipped_document_back_compressed() {
+    let dir = TempDir::new("gzip-replace");
+    let document = dir.write("doc.xml.gz", compress(UNFORMATTED));
+
+    let output = xml_pretty()
+        .arg("--replace")
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let written = fs::read(&document).unwrap();
+    assert!(written.starts_with(&[0x1f, 0x8b]));
+    assert_eq!(decompress(&written), FORMATTED);
+}
+
+#[test]
+fn check_compares_the_decompressed_document() {
+    let dir = TempDir::new("gzip-check");
+    let formatted = dir.write("formatted.xml.gz", compress(FORMATTED));
+    let unformatted = dir.write("unformatted.xml.gz", compress(UNFORMATTED));
+
+    let output = xml_pretty().arg("check").arg(&formatted).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = xml_pretty()
+        .arg("check")
+        .arg(&unformatted)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("xml-pretty --lint failed"),
+        "{}",
+        stderr(&output)
+    );
+    // Checking leaves the file as it was.
+    assert_eq!(decompress(&fs::read(&unformatted).unwrap()), UNFORMATTED);
+}
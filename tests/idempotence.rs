@@ -0,0 +1,224 @@
+//! Formatting a formatted document changes nothing, whatever the settings: random small
+//! documents are formatted twice with random flags and the two outputs compared. Where the
+//! flags don't change the data, the output must also be equivalent to the input.
+
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{format, xml_pretty, TempDir};
+use proptest::{prelude::*, test_runner::FileFailurePersistence};
+
+const NAMES: &[&str] = &["a", "b", "item", "ns:e", "x-y", "long-element-name"];
+const ATTRIBUTE_NAMES: &[&str] = &[
+    "id",
+    "d",
+    "x",
+    "class",
+    "ns:attr",
+    "disabled",
+    "title",
+    "xml:space",
+];
+
+fn attribute_value() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z ]{0,12}",
+        Just("M1.2345,2.3456L3-4".to_string()),
+        Just("&quot;x&quot; 'y'".to_string()),
+        Just("&lt;&gt;&amp;".to_string()),
+        Just("line\nbreak\ttab".to_string()),
+        Just("a > b".to_string()),
+        Just("12.5px".to_string()),
+        Just("é中🙂".to_string()),
+        Just("&#10;&#9;&#13;".to_string()),
+        Just("preserve".to_string()),
+    ]
+}
+
+fn text() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z]{1,8}( [a-z]{1,8}){0,12}",
+        Just(" spaced  text ".to_string()),
+        Just("a &amp; b &lt;tag&gt;".to_string()),
+        Just("multi\nline\n  text".to_string()),
+        Just("&#160;&#x1F600; é中".to_string()),
+        Just("\"q\" 'q' a > b".to_string()),
+        Just("  ".to_string()),
+        Just("\n\n".to_string()),
+        Just("crlf\r\n  line".to_string()),
+    ]
+}
+
+fn comment() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-z ]{0,30}",
+        Just(" multi\n line ".to_string()),
+        Just(" ==== title ==== ".to_string()),
+        Just(" a > b - c ".to_string()),
+    ]
+    .prop_map(|text| format!("<!--{}-->", text))
+}
+
+fn cdata() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just("x".to_string()),
+        Just(" <b> & ]] ".to_string()),
+        Just("a\nb".to_string()),
+    ]
+    .prop_map(|text| format!("<![CDATA[{}]]>", text))
+}
+
+fn start_tag() -> impl Strategy<Value = (String, String)> {
+    (
+        prop::sample::select(NAMES),
+        prop::collection::btree_map(
+            prop::sample::select(ATTRIBUTE_NAMES),
+            attribute_value(),
+            0..4,
+        ),
+    )
+        .prop_map(|(name, attributes)| {
+            let attributes = attributes
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = value.replace('"', "&quot;");
+                    format!(" {}=\"{}\"", name, value)
+                })
+                .collect::<String>();
+            (name.to_string(), attributes)
+        })
+}
+
+fn element() -> impl Strategy<Value = String> {
+    let leaf = start_tag().prop_map(|(name, attributes)| format!("<{}{}/>", name, attributes));
+    leaf.prop_recursive(4, 32, 5, |inner| {
+        (
+            start_tag(),
+            prop::collection::vec(
+                prop_oneof![
+                    4 => inner,
+                    3 => text(),
+                    1 => comment(),
+                    1 => cdata(),
+                ],
+                0..5,
+            ),
+        )
+            .prop_map(|((name, attributes), children)| {
+                format!("<{0}{1}>{2}</{0}>", name, attributes, children.concat())
+            })
+    })
+}
+
+/// Whether the document has a processing instruction, which xmlem drops.
+fn has_pi(document: &str) -> bool {
+    document.contains("<?pi")
+}
+
+fn document() -> impl Strategy<Value = String> {
+    (
+        prop_oneof![
+            Just(""),
+            Just("<?xml version=\"1.0\"?>\n"),
+            Just("<?xml version=\"1.0\" encoding=\"UTF-8\"?>"),
+            Just("<!-- top -->\n"),
+            Just("<!DOCTYPE r>\n"),
+        ],
+        element(),
+        prop_oneof![
+            4 => Just(""),
+            1 => Just("<?pi data?>"),
+        ],
+    )
+        .prop_map(|(prolog, root, pi)| {
+            // Every prefix used is declared on the root element.
+            let space = root.find([' ', '/', '>']).unwrap();
+            format!(
+                "{}{}{} xmlns=\"urn:d\" xmlns:ns=\"urn:n\"{}",
+                prolog,
+                pi,
+                &root[..space],
+                &root[space..]
+            )
+        })
+}
+
+/// A group of flags, and whether it changes what the document says rather than only how it
+/// is laid out.
+const SETTINGS: &[(&[&str], bool)] = &[
+    (&["-l", "1"], false),
+    (&["-l", "40"], false),
+    (&["--indent", "0"], false),
+    (&["--indent", "4"], false),
+    (&["-e", "0"], false),
+    (&["-e", "3"], false),
+    (&["-H"], false),
+    (&["--no-text-indent"], false),
+    (&["--prolog-blank-line", "no"], false),
+    (&["--no-wrap-root"], false),
+    (&["--omit-utf8-encoding"], false),
+    (&["--fix-encoding-declaration"], false),
+    (&["--compat", "xmllint"], true),
+    (&["--sort-attributes"], true),
+    (&["--xmlns-sort", "uri", "--sort-attributes"], true),
+    (&["--sort-children", "*"], true),
+    (&["--wrap-comments"], true),
+    (&["--wrap-comments", "--comment-width", "20"], true),
+    (&["--round-numbers", "2"], true),
+    (&["--expand-boolean-attr", "disabled"], true),
+    (&["--strip-attr", "class"], true),
+    (&["--normalize-attr-case", "id=lower"], true),
+];
+
+fn settings() -> impl Strategy<Value = Vec<(&'static [&'static str], bool)>> {
+    prop::sample::subsequence(SETTINGS, 0..=4)
+}
+
+/// Whether `xml-pretty diff` finds the two documents equivalent.
+fn is_equivalent(dir: &Path, left: &str, right: &str) -> bool {
+    let left_path = dir.join("left.xml");
+    let right_path = dir.join("right.xml");
+    fs::write(&left_path, left).unwrap();
+    fs::write(&right_path, right).unwrap();
+    let output = xml_pretty()
+        .arg("diff")
+        .arg(&left_path)
+        .arg(&right_path)
+        .output()
+        .unwrap();
+    output.status.success()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 256,
+        failure_persistence: Some(Box::new(FileFailurePersistence::WithSource("regressions"))),
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn formatting_twice_changes_nothing(document in document(), settings in settings()) {
+        let args = settings
+            .iter()
+            .flat_map(|(flags, _)| flags.iter().copied())
+            .collect::<Vec<_>>();
+
+        // Every generated document is well-formed, so it must format.
+        let once = format(&args, &document).map_err(TestCaseError::fail)?;
+
+        let twice = format(&args, &once).map_err(TestCaseError::fail)?;
+        prop_assert_eq!(&twice, &once, "flags {:?}", args);
+
+        let changes_data = settings.iter().any(|(_, changes_data)| *changes_data);
+        if !changes_data && !has_pi(&document) {
+            let dir = TempDir::new("idempotence");
+            prop_assert!(
+                is_equivalent(&dir.0, &document, &once),
+                "not equivalent with flags {:?}:\n{}",
+                args,
+                once
+            );
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! `--invalid-chars`, for control characters and stray byte order marks in scraped documents.
+
+mod common;
+
+use common::{run_with_stdin, stderr, stdout, xml_pretty};
+
+/// A U+0001 control character, and a byte order mark in the middle of the text.
+const DOCUMENT: &[u8] = b"<r>a\x01b<s>\xEF\xBB\xBFc</s></r>";
+
+fn formatted(args: &[&str]) -> (Option<i32>, String, String) {
+    let output = run_with_stdin(xml_pretty().args(args).arg("--stdin"), DOCUMENT);
+    (output.status.code(), stdout(&output), stderr(&output))
+}
+
+#[test]
+fn error_refuses_the_document_with_the_position() {
+    let error = (
+        Some(1),
+        String::new(),
+        "Error: Failed to prettify from stdin\n\n\
+         Caused by:\n    invalid character U+0001 at line 1, column 5 (use --invalid-chars strip or entity to accept it)\n"
+            .to_string(),
+    );
+    assert_eq!(formatted(&[]), error);
+    assert_eq!(formatted(&["--invalid-chars", "error"]), error);
+}
+
+#[test]
+fn strip_removes_the_characters_with_warnings() {
+    assert_eq!(
+        formatted(&["--invalid-chars", "strip"]),
+        (
+            Some(0),
+            "<r>\n  ab\n  <s>\n    c\n  </s>\n</r>\n".to_string(),
+            "WARNING: document from stdin: removed 1 invalid character, the first U+0001 at line 1, column 5\n\
+             WARNING: document from stdin: removed 1 byte order mark after the start of the document, the first at line 1, column 10\n"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn entity_writes_character_references_with_warnings() {
+    assert_eq!(
+        formatted(&["--invalid-chars", "entity"]),
+        (
+            Some(0),
+            "<r>\n  a&#x0001;b\n  <s>\n    &#xFEFF;c\n  </s>\n</r>\n".to_string(),
+            "WARNING: document from stdin: kept 1 invalid character, the first U+0001 at line 1, column 5; the output is not well-formed\n\
+             WARNING: document from stdin: found 1 byte order mark after the start of the document, the first at line 1, column 10\n"
+                .to_string()
+        )
+    );
+}
@@ -0,0 +1,59 @@
+//! The findings of the `check` rules each point at where they are in the source.
+
+mod common;
+
+use common::{stderr, xml_pretty, TempDir};
+
+#[test]
+fn rule_findings_have_lines_and_columns() {
+    let dir = TempDir::new("lint-rules");
+    let document = dir.write(
+        "doc.xml",
+        "<?xml version=\"1.0\"?>\n\
+         <!-- <fake id=\"X\"/> -->\n\
+         <r xmlns=\"urn:x\">\n  \
+         <b id=\"ABC\"\n     d=\"M1.23456,2\"/>\n  \
+         <a>x</a>\n  \
+         <c xmlns=\"\" z=\"1\" y=\"2\"/>\n\
+         </r>\n",
+    );
+
+    let output = xml_pretty()
+        .args([
+            "check",
+            "--check-sorted-attributes",
+            "--check-sorted-children",
+            "r",
+            "--check-default-namespace",
+            "urn:x",
+            "--normalize-attr-case",
+            "id=lower",
+            "--round-numbers",
+            "2",
+        ])
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let message = stderr(&output);
+    let findings = message
+        .lines()
+        .skip_while(|line| !line.starts_with("Error: xml-pretty --lint failed"))
+        .skip(1)
+        .take_while(|line| line.starts_with("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        [
+            "  5:6: attribute 'd' of /r/b is out of order",
+            "  7:21: attribute 'y' of /r/c is out of order",
+            "  6:3: child <a> of /r is out of order",
+            "  7:3: element /r/c is in no namespace, not 'urn:x'",
+            "  4:6: attribute 'id' of /r/b is \"ABC\", expected \"abc\"",
+            "  5:6: attribute 'd' of /r/b has 1.23456, expected 1.23",
+        ],
+        "{}",
+        message
+    );
+}
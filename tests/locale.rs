@@ -0,0 +1,52 @@
+//! The output doesn't depend on the locale: sorting is by code point, case mapping is not
+//! Turkish even under a Turkish locale, and numbers keep their `.`.
+
+mod common;
+
+use common::{run_with_stdin, stderr, xml_pretty};
+
+const DOCUMENT: &str = "<r ä=\"1\" z=\"2\" I=\"3\" a=\"4\" B=\"5\">\
+    <item id=\"TITLE\" d=\"M1.23456,2.5\"/><Item id=\"ıi\"/><item id=\"Ä\"/></r>";
+
+const ARGS: &[&str] = &[
+    "--sort-attributes",
+    "--sort-children",
+    "r",
+    "--sort-children-by",
+    "@id",
+    "--normalize-attr-case",
+    "id=lower",
+    "--round-numbers",
+    "2",
+    "--stdin",
+];
+
+/// Formats the document with every locale variable set to `locale`.
+fn format_in(locale: &str) -> String {
+    let mut command = xml_pretty();
+    for name in ["LC_ALL", "LANG", "LC_COLLATE", "LC_CTYPE", "LC_NUMERIC"] {
+        command.env(name, locale);
+    }
+    let output = run_with_stdin(command.args(ARGS), DOCUMENT);
+    assert!(output.status.success(), "{}", stderr(&output));
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn output_is_the_same_in_every_locale() {
+    let expected = format_in("C");
+    assert_eq!(
+        expected,
+        "<r B=\"5\" I=\"3\" a=\"4\" z=\"2\" ä=\"1\">\n  \
+         <item d=\"M1.23,2.5\" id=\"title\" />\n  \
+         <item id=\"ä\" />\n  \
+         <Item id=\"ıi\" />\n\
+         </r>\n"
+    );
+
+    // The locales need not be installed: the variables are set either way, and a program that
+    // consulted them would fall back differently from C.
+    for locale in ["tr_TR.UTF-8", "de_DE.UTF-8", "sv_SE.UTF-8", "ja_JP.UTF-8"] {
+        assert_eq!(format_in(locale), expected, "{}", locale);
+    }
+}
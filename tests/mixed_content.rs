@@ -0,0 +1,42 @@
+//! Text and elements in mixed content keep their order and their text, however they are
+//! written out.
+
+mod common;
+
+use common::{format, stderr, stdout, xml_pretty, TempDir};
+
+const CASES: &[(&str, &str)] = &[
+    ("<p>a<b/>c</p>", "<p>\n  a\n  <b/>\n  c\n</p>\n"),
+    ("<p><b/>a<c/></p>", "<p>\n  <b/>\n  a\n  <c/>\n</p>\n"),
+    ("<p>a<b/></p>", "<p>\n  a\n  <b/>\n</p>\n"),
+    ("<p><b/>a</p>", "<p>\n  <b/>\n  a\n</p>\n"),
+    (
+        "<p>a <b>x</b>, c<br/>d</p>",
+        "<p>\n  a\n  <b>\n    x\n  </b>\n  , c\n  <br/>\n  d\n</p>\n",
+    ),
+];
+
+#[test]
+fn pretty_output_keeps_each_fragment_in_place() {
+    for (input, expected) in CASES {
+        assert_eq!(format(&[], input).as_deref(), Ok(*expected), "{}", input);
+    }
+}
+
+#[test]
+fn unindented_and_minified_output_match_the_source() {
+    let dir = TempDir::new("mixed-content");
+    for (i, (input, _)) in CASES.iter().enumerate() {
+        assert_eq!(
+            format(&["--no-text-indent"], input),
+            Ok(format!("{}\n", input)),
+            "{}",
+            input
+        );
+
+        let document = dir.write(&format!("{}.xml", i), input);
+        let output = xml_pretty().arg("minify").arg(&document).output().unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+        assert_eq!(stdout(&output), format!("{}\n", input));
+    }
+}
@@ -0,0 +1,63 @@
+//! `--no-sort-attributes-in`, which keeps the attribute order of chosen elements, and with
+//! `/**` of their descendants too.
+
+mod common;
+
+use common::{format, stderr, xml_pretty, TempDir};
+
+#[test]
+fn exempt_elements_keep_their_attribute_order() {
+    let document = "<r c=\"1\" a=\"2\">\
+                    <keep c=\"1\" a=\"2\"><in c=\"1\" a=\"2\"/></keep>\
+                    <tree c=\"1\" a=\"2\"><in c=\"1\" xmlns:z=\"urn:z\" a=\"2\"/></tree>\
+                    </r>";
+    assert_eq!(
+        format(
+            &[
+                "--sort-attributes",
+                "--no-sort-attributes-in",
+                "keep, tree/**"
+            ],
+            document
+        )
+        .unwrap(),
+        "<r a=\"2\" c=\"1\">\n  \
+         <keep c=\"1\" a=\"2\">\n    \
+         <in a=\"2\" c=\"1\" />\n  \
+         </keep>\n  \
+         <tree c=\"1\" a=\"2\">\n    \
+         <in c=\"1\" xmlns:z=\"urn:z\" a=\"2\" />\n  \
+         </tree>\n\
+         </r>\n"
+    );
+}
+
+#[test]
+fn exempt_elements_pass_the_sorted_attributes_lint() {
+    let dir = TempDir::new("no-sort-attributes-in");
+    let document = dir.write(
+        "doc.xml",
+        "<r a=\"2\" c=\"1\">\n  <keep c=\"1\" a=\"2\">\n    <in a=\"2\" c=\"1\" />\n  </keep>\n</r>\n",
+    );
+    let check = |exemption: &[&str]| {
+        xml_pretty()
+            .args(["check", "--check-sorted-attributes"])
+            .args(exemption)
+            .arg(&document)
+            .output()
+            .unwrap()
+    };
+
+    let output = check(&[]);
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("2:15: attribute 'a' of /r/keep is out of order"),
+        "{}",
+        stderr(&output)
+    );
+
+    for exemption in ["keep", "keep/**"] {
+        let output = check(&["--no-sort-attributes-in", exemption]);
+        assert!(output.status.success(), "{}", stderr(&output));
+    }
+}
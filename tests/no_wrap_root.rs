@@ -0,0 +1,59 @@
+//! `--no-wrap-root`, which keeps the root element's start tag on one line however long it is,
+//! while the rest of the document wraps as usual.
+
+mod common;
+
+use common::{format, run_with_stdin, stderr, xml_pretty};
+
+const DOCUMENT: &str = "<root xmlns=\"urn:aaaaaaaaaaaaaaaaaaaa\" xmlns:b=\"urn:bbbbbbbbbbbbbbbbbbbbbbbbb\" alpha=\"1111111111\" beta=\"2222222222\">\
+                        <child one=\"111111111111111\" two=\"2222222222222222\" three=\"333333333333\"/>\
+                        </root>";
+
+const FORMATTED: &str = "<root xmlns=\"urn:aaaaaaaaaaaaaaaaaaaa\" xmlns:b=\"urn:bbbbbbbbbbbbbbbbbbbbbbbbb\" alpha=\"1111111111\" beta=\"2222222222\">\n  \
+                         <child one=\"111111111111111\"\n    two=\"2222222222222222\"\n    three=\"333333333333\" />\n\
+                         </root>\n";
+
+const ARGS: &[&str] = &["--no-wrap-root", "--max-line-length", "60"];
+
+#[test]
+fn only_the_root_start_tag_stays_on_one_line() {
+    assert_eq!(format(ARGS, DOCUMENT).unwrap(), FORMATTED);
+
+    // Without the flag the root wraps like any other element.
+    assert!(format(&ARGS[1..], DOCUMENT)
+        .unwrap()
+        .starts_with("<root xmlns=\"urn:aaaaaaaaaaaaaaaaaaaa\"\n  xmlns:b="));
+
+    // Line breaks in attribute values are escaped, so they cannot end up joined.
+    assert_eq!(
+        format(
+            &["--no-wrap-root", "--max-line-length", "40"],
+            "<?xml version=\"1.0\"?>\n<root a=\"line one\nline two\" bbbbbbbbbbbbbbbbbbbbbbbbb=\"2\"/>"
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\"?>\n\n<root a=\"line one&#x000A;line two\" bbbbbbbbbbbbbbbbbbbbbbbbb=\"2\" />\n"
+    );
+}
+
+#[test]
+fn output_is_stable_and_passes_check() {
+    assert_eq!(format(ARGS, FORMATTED).unwrap(), FORMATTED);
+
+    let output = run_with_stdin(
+        xml_pretty().arg("check").args(ARGS).arg("--stdin"),
+        FORMATTED,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // A check without the flag expects the root to be wrapped.
+    let output = run_with_stdin(
+        xml_pretty().arg("check").args(&ARGS[1..]).arg("--stdin"),
+        FORMATTED,
+    );
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("(attribute-wrapping)"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,83 @@
+//! `--normalize-attr-case`, which changes the case of the values of the listed attributes only.
+
+mod common;
+
+use common::{format, stderr, xml_pretty, TempDir};
+
+const DOCUMENT: &str = "<r xmlns:c=\"urn:c\" enabled=\"TRUE\" c:on=\"True\" other=\"TRUE\" c:enabled=\"FaLsE\"><s enabled=\"False\" visible=\"No\"/></r>";
+
+#[test]
+fn only_listed_attributes_are_normalized() {
+    assert_eq!(
+        format(
+            &[
+                "--normalize-attr-case",
+                "enabled=lower",
+                "--normalize-attr-case",
+                "c:on=upper",
+            ],
+            DOCUMENT
+        )
+        .unwrap(),
+        "<r xmlns:c=\"urn:c\" enabled=\"true\" c:on=\"TRUE\" other=\"TRUE\" c:enabled=\"FaLsE\">\n  \
+         <s enabled=\"false\" visible=\"No\" />\n\
+         </r>\n"
+    );
+
+    // Without the option, values are data like any other.
+    assert_eq!(
+        format(&[], DOCUMENT).unwrap(),
+        "<r xmlns:c=\"urn:c\" enabled=\"TRUE\" c:on=\"True\" other=\"TRUE\" c:enabled=\"FaLsE\">\n  \
+         <s enabled=\"False\" visible=\"No\" />\n\
+         </r>\n"
+    );
+}
+
+#[test]
+fn lint_reports_values_not_in_the_normalized_case() {
+    let dir = TempDir::new("normalize-attr-case");
+    let document = dir.write(
+        "doc.xml",
+        "<r xmlns:c=\"urn:c\" enabled=\"TRUE\" c:enabled=\"FaLsE\">\n  <s c:on=\"True\" />\n</r>\n",
+    );
+    let output = xml_pretty()
+        .args([
+            "check",
+            "--normalize-attr-case",
+            "enabled=lower",
+            "--normalize-attr-case",
+            "c:on=upper",
+        ])
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let message = stderr(&output);
+    let findings = message
+        .lines()
+        .filter(|line| line.starts_with("  "))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        findings,
+        [
+            "  1:20: attribute 'enabled' of /r is \"TRUE\", expected \"true\"",
+            "  2:6: attribute 'c:on' of /r/s is \"True\", expected \"TRUE\"",
+        ],
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn unknown_cases_are_refused() {
+    let output = xml_pretty()
+        .args(["--normalize-attr-case", "enabled=title", "--stdin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("expected 'NAME=lower' or 'NAME=upper', got 'enabled=title'"),
+        "{}",
+        stderr(&output)
+    );
+}
@@ -0,0 +1,38 @@
+//! `--omit-utf8-encoding`, which drops `encoding="UTF-8"` from the XML declaration.
+
+mod common;
+
+use common::format;
+
+#[test]
+fn only_a_utf8_encoding_is_dropped() {
+    let cases = [
+        (
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?><r/>",
+            "<?xml version=\"1.0\" standalone=\"yes\"?>\n\n<r/>\n",
+        ),
+        (
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><r/>",
+            "<?xml version=\"1.0\"?>\n\n<r/>\n",
+        ),
+        (
+            "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?><r/>",
+            "<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>\n\n<r/>\n",
+        ),
+        ("<r/>", "<r/>\n"),
+    ];
+    for (input, expected) in cases {
+        assert_eq!(
+            format(&["--omit-utf8-encoding"], input).unwrap(),
+            expected,
+            "{}",
+            input
+        );
+    }
+
+    // Without the flag the declaration is kept as it is.
+    assert_eq!(
+        format(&[], "<?xml version=\"1.0\" encoding=\"UTF-8\"?><r/>").unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\n<r/>\n"
+    );
+}
@@ -0,0 +1,66 @@
+//! The prettier-style `--indent-size`, `--print-width` and `--indent-style`, which map onto
+//! xml-pretty's own options.
+
+mod common;
+
+use common::{format, stderr, xml_pretty};
+
+const DOCUMENT: &str = "<r aaaaaaa=\"1\" bbbbbbbbbbb=\"2\" ccccccccccccc=\"3\"><s><t/></s></r>";
+
+#[test]
+fn aliases_give_the_same_output_as_the_original_options() {
+    let pairs: &[(&[&str], &[&str])] = &[
+        (&["--indent-size", "4"], &["--indent", "4"]),
+        (&["--print-width", "20"], &["--max-line-length", "20"]),
+        (
+            &["--indent-size", "4", "--indent-style", "space"],
+            &["--indent", "4"],
+        ),
+        // The original option wins.
+        (&["--indent", "2", "--indent-size", "4"], &["--indent", "2"]),
+        (
+            &["--max-line-length", "200", "--print-width", "20"],
+            &["--max-line-length", "200"],
+        ),
+    ];
+    for (alias, original) in pairs {
+        assert_eq!(
+            format(alias, DOCUMENT),
+            format(original, DOCUMENT),
+            "{:?}",
+            alias
+        );
+    }
+    assert_eq!(
+        format(&["--indent-size", "4", "--print-width", "20"], DOCUMENT).unwrap(),
+        "<r aaaaaaa=\"1\"\n    bbbbbbbbbbb=\"2\"\n    ccccccccccccc=\"3\">\n    <s>\n        <t/>\n    </s>\n</r>\n"
+    );
+}
+
+#[test]
+fn tab_indentation_needs_reindent_only() {
+    let output = xml_pretty()
+        .args(["--indent-size", "4", "--indent-style", "tab", "--stdin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stderr(&output),
+        "ERROR: --indent-style tab needs --reindent-only; xmlem only indents with spaces.\n"
+    );
+
+    assert_eq!(
+        format(
+            &[
+                "--reindent-only",
+                "--indent-size",
+                "4",
+                "--indent-style",
+                "tab"
+            ],
+            "<r>\n    <s>\n        <t/>\n    </s>\n</r>\n"
+        )
+        .unwrap(),
+        "<r>\n\t<s>\n\t\t<t/>\n\t</s>\n</r>\n"
+    );
+}
@@ -0,0 +1,64 @@
+//! The prolog is written one item per line, with a blank line before the root element unless
+//! `--prolog-blank-line no` is given.
+
+mod common;
+
+use common::{format, run_with_stdin, xml_pretty};
+
+const VARIANTS: &[&str] = &[
+    "<?xml version=\"1.0\"?><!-- License --><r/><!-- after --><!-- more -->",
+    "<?xml version=\"1.0\"?>\n<!-- License -->\n<r/>\n<!-- after -->\n<!-- more -->\n",
+    "<?xml version=\"1.0\"?>\n\n\n<!-- License -->\n\n<r/>\n\n<!-- after -->\n\n\n<!-- more -->\n",
+];
+
+#[test]
+fn prolog_variants_normalize_to_one_layout() {
+    let cases: &[(&[&str], &str)] = &[
+        (
+            &[],
+            "<?xml version=\"1.0\"?>\n<!-- License -->\n\n<r/>\n<!-- after -->\n<!-- more -->\n",
+        ),
+        (
+            &["--prolog-blank-line", "yes"],
+            "<?xml version=\"1.0\"?>\n<!-- License -->\n\n<r/>\n<!-- after -->\n<!-- more -->\n",
+        ),
+        (
+            &["--prolog-blank-line", "no"],
+            "<?xml version=\"1.0\"?>\n<!-- License -->\n<r/>\n<!-- after -->\n<!-- more -->\n",
+        ),
+        // xmllint writes no blank line.
+        (
+            &["--compat", "xmllint"],
+            "<?xml version=\"1.0\"?>\n<!-- License -->\n<r/>\n<!-- after -->\n<!-- more -->\n",
+        ),
+    ];
+    for (args, expected) in cases {
+        for input in VARIANTS {
+            assert_eq!(
+                format(args, input).as_deref(),
+                Ok(*expected),
+                "{:?} {:?}",
+                args,
+                input
+            );
+        }
+    }
+}
+
+#[test]
+fn check_expects_the_blank_line_by_default() {
+    let check = |args: &[&str], input: &str| {
+        run_with_stdin(xml_pretty().arg("check").args(args).arg("--stdin"), input)
+            .status
+            .success()
+    };
+    let with = "<?xml version=\"1.0\"?>\n\n<r/>\n";
+    let without = "<?xml version=\"1.0\"?>\n<r/>\n";
+
+    assert!(check(&[], with));
+    assert!(!check(&[], without));
+    assert!(check(&["--prolog-blank-line", "no"], without));
+    assert!(!check(&["--prolog-blank-line", "no"], with));
+    // A document without a prolog has nothing to separate.
+    assert!(check(&[], "<r/>\n"));
+}
@@ -0,0 +1,40 @@
+//! Documents that are only a prolog, such as DTD carriers with a DOCTYPE and no root element.
+
+mod common;
+
+use common::{format, stderr, stdout, xml_pretty, TempDir};
+
+const DTD_CARRIER: &str = "<!DOCTYPE x [ <!ENTITY foo \"bar\"> ]>";
+
+#[test]
+fn doctype_with_an_internal_subset_round_trips_without_a_root() {
+    assert_eq!(
+        format(&[], DTD_CARRIER).unwrap(),
+        format!("{}\n", DTD_CARRIER)
+    );
+    assert_eq!(
+        format(&[], format!("<?xml version=\"1.0\"?>\n{}\n", DTD_CARRIER)).unwrap(),
+        format!("<?xml version=\"1.0\"?>\n{}\n", DTD_CARRIER)
+    );
+
+    // The internal subset is kept as written, across lines too.
+    let multiline = "<!DOCTYPE x [\n  <!ENTITY foo \"bar\">\n  <!ELEMENT x (#PCDATA)>\n]>\n";
+    assert_eq!(format(&[], multiline).unwrap(), multiline);
+}
+
+#[test]
+fn dtd_carriers_pass_check_and_need_no_fragment() {
+    let dir = TempDir::new("prolog-only");
+    let document = dir.write("entities.xml", format!("{}\n", DTD_CARRIER));
+
+    let output = xml_pretty().arg("check").arg(&document).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    let output = xml_pretty().arg("minify").arg(&document).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), format!("{}\n", DTD_CARRIER));
+
+    let output = xml_pretty().arg("tree").arg(&document).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
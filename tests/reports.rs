@@ -0,0 +1,115 @@
+//! The shape of the `--summary-json` and `--log-file` reports, and the timings in them.
+
+mod common;
+
+use std::fs;
+
+use common::{stderr, xml_pretty, TempDir};
+
+const PHASES: &[&str] = &[
+    "read_ms",
+    "parse_ms",
+    "serialize_ms",
+    "compare_ms",
+    "write_ms",
+    "total_ms",
+];
+
+/// The `"timings"` objects in `json`, each as its field names and values in order.
+fn timings(json: &str) -> Vec<Vec<(String, f64)>> {
+    json.match_indices("\"timings\":{")
+        .map(|(i, key)| {
+            let rest = &json[i + key.len()..];
+            rest[..rest.find('}').unwrap()]
+                .split(',')
+                .map(|field| {
+                    let (name, value) = field.split_once(':').unwrap();
+                    (name.trim_matches('"').to_string(), value.parse().unwrap())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Checks that `timings` has every phase in order, and that the phases fit in the total.
+fn assert_phases(timings: &[(String, f64)]) {
+    let names = timings
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, PHASES);
+    assert!(timings.iter().all(|(_, ms)| *ms >= 0.0));
+    let phases = timings[..5].iter().map(|(_, ms)| ms).sum::<f64>();
+    // Each value is rounded to a microsecond.
+    assert!(phases <= timings[5].1 + 0.003, "{:?}", timings);
+}
+
+#[test]
+fn reports_have_timings_for_the_run_and_each_document() {
+    let dir = TempDir::new("reports");
+    let formatted = dir.write("formatted.xml", "<r>\n  <a/>\n</r>\n");
+    let unformatted = dir.write("unformatted.xml", "<r><a/></r>");
+    let summary_path = dir.path("summary.json");
+    let log_path = dir.path("log.jsonl");
+
+    let output = xml_pretty()
+        .arg("check")
+        .arg("--summary-json")
+        .arg(&summary_path)
+        .arg("--log-file")
+        .arg(&log_path)
+        .arg(&formatted)
+        .arg(&unformatted)
+        .output()
+        .unwrap();
+    assert!(!output.status.success(), "{}", stderr(&output));
+
+    let summary = fs::read_to_string(&summary_path).unwrap();
+    assert!(
+        summary.starts_with(
+            "{\"version\":2,\"tool\":\"xml-pretty\",\"total\":2,\"changed\":1,\"unchanged\":1,\"printed\":0,\"errored\":0,\"skipped\":0,\"timings\":{\"read_ms\":"
+        ),
+        "{}",
+        summary
+    );
+    assert!(summary.ends_with("}]}\n"), "{}", summary);
+    let peak_rss = summary.split("\"peak_rss_kib\":").nth(1).unwrap();
+    let peak_rss = &peak_rss[..peak_rss.find(',').unwrap()];
+    assert!(
+        peak_rss == "null" || peak_rss.parse::<u64>().is_ok(),
+        "{}",
+        summary
+    );
+    assert!(summary.contains(&format!(
+        "\"files\":[{{\"path\":{:?},\"status\":\"checked\",\"timings\":{{",
+        formatted.display().to_string()
+    )));
+    assert!(summary.contains(&format!(
+        "{{\"path\":{:?},\"status\":\"unformatted\",\"categories\":[\"final-newline\",\"indentation\"],\"error\":{{\"message\":",
+        unformatted.display().to_string()
+    )));
+
+    // The run, then each document.
+    let summary_timings = timings(&summary);
+    assert_eq!(summary_timings.len(), 3);
+    for timings in &summary_timings {
+        assert_phases(timings);
+    }
+    assert!(summary_timings[0][5].1 >= summary_timings[1][5].1 + summary_timings[2][5].1);
+
+    // The header, then one line per document with the same timings as in the summary.
+    let log = fs::read_to_string(&log_path).unwrap();
+    let lines = log.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 3);
+    assert!(timings(lines[0]).is_empty());
+    assert!(!lines[1].contains("\"categories\""), "{}", lines[1]);
+    assert!(
+        lines[2].contains(",\"categories\":[\"final-newline\",\"indentation\"],"),
+        "{}",
+        lines[2]
+    );
+    for (line, expected) in lines[1..].iter().zip(&summary_timings[1..]) {
+        assert!(line.ends_with("}}"), "{}", line);
+        assert_eq!(&timings(line), std::slice::from_ref(expected));
+    }
+}
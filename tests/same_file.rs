@@ -0,0 +1,89 @@
+//! A document reached through a symlink is the same document: it is processed once, and is not
+//! taken as a separate output file.
+
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, os::unix::fs::symlink};
+
+use common::{stderr, stdout, xml_pretty, TempDir};
+
+const DOCUMENT: &str = "<r><a/></r>";
+
+#[test]
+fn file_and_symlink_to_it_are_processed_once() {
+    let dir = TempDir::new("same-file");
+    let file = dir.write("doc.xml", DOCUMENT);
+    let link = dir.path("link.xml");
+    symlink(&file, &link).unwrap();
+
+    let output = xml_pretty().arg(&file).arg(&link).output().unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "<r>\n  <a/>\n</r>\n");
+
+    let log = dir.path("log.jsonl");
+    let output = xml_pretty()
+        .arg("--replace")
+        .arg("--log-file")
+        .arg(&log)
+        .arg(&link)
+        .arg(&file)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    let log = fs::read_to_string(&log).unwrap();
+    let entries = log.lines().skip(1).collect::<Vec<_>>();
+    assert_eq!(entries.len(), 1, "{}", log);
+    assert!(entries[0].contains("\"action\":\"rewritten\""), "{}", log);
+    assert_eq!(fs::read_to_string(&file).unwrap(), "<r>\n  <a/>\n</r>\n");
+}
+
+#[test]
+fn file_given_twice_is_processed_once_with_a_warning() {
+    let dir = TempDir::new("same-file-twice");
+    let file = dir.write("doc.xml", DOCUMENT);
+    let link = dir.path("link.xml");
+    symlink(&file, &link).unwrap();
+
+    let output = xml_pretty()
+        .args(["--path-style", "as-given"])
+        .arg(&file)
+        .arg(&file)
+        .arg(&link)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "<r>\n  <a/>\n</r>\n");
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "WARNING: '{file}' is the same file as '{file}', so it is only processed once.\n\
+             WARNING: '{link}' is the same file as '{file}', so it is only processed once.\n",
+            file = file.display(),
+            link = link.display()
+        )
+    );
+}
+
+#[test]
+fn output_through_a_symlink_to_the_input_is_refused() {
+    let dir = TempDir::new("same-file-output");
+    let file = dir.write("doc.xml", DOCUMENT);
+    let link = dir.path("link.xml");
+    symlink(&file, &link).unwrap();
+
+    let output = xml_pretty()
+        .arg(&file)
+        .arg("-o")
+        .arg(&link)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("is the input document; use --replace to format it in place"),
+        "{}",
+        stderr(&output)
+    );
+    assert_eq!(fs::read_to_string(&file).unwrap(), DOCUMENT);
+}
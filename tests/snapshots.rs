@@ -0,0 +1,69 @@
+//! `--snapshot-dir` and `--accept`: snapshots are written, then checked against until the
+//! document changes.
+
+mod common;
+
+use std::fs;
+
+use common::{stderr, xml_pretty, TempDir};
+
+#[test]
+fn snapshot_is_written_matched_and_then_differs() {
+    let dir = TempDir::new("snapshots");
+    let document = dir.write("doc.xml", "<r><a>1</a></r>");
+    let snapshots = dir.path("snapshots");
+
+    let check = || {
+        xml_pretty()
+            .arg("--snapshot-dir")
+            .arg(&snapshots)
+            .arg(&document)
+            .output()
+            .unwrap()
+    };
+
+    let output = check();
+    assert!(!output.status.success());
+    assert!(
+        stderr(&output).contains("does not exist; use --accept to create it"),
+        "{}",
+        stderr(&output)
+    );
+
+    let output = xml_pretty()
+        .arg("--snapshot-dir")
+        .arg(&snapshots)
+        .arg("--accept")
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+    let snapshot = snapshots.join("doc.xml");
+    assert_eq!(
+        fs::read_to_string(&snapshot).unwrap(),
+        "<r>\n  <a>\n    1\n  </a>\n</r>\n"
+    );
+
+    let output = check();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(output.stdout.is_empty());
+
+    fs::write(&document, "<r><a>2</a></r>").unwrap();
+    let output = check();
+    assert!(!output.status.success());
+    let message = stderr(&output);
+    assert!(
+        message.contains(&format!(
+            "differs from snapshot '{}' at line 3, column 5:\n  -     1\n  +     2",
+            snapshot.display()
+        )),
+        "{}",
+        message
+    );
+    // A failed check leaves the snapshot as it was.
+    assert_eq!(
+        fs::read_to_string(&snapshot).unwrap(),
+        "<r>\n  <a>\n    1\n  </a>\n</r>\n"
+    );
+}
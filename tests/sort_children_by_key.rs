@@ -0,0 +1,86 @@
+//! `--sort-children-by key=...`, which sorts elements such as a pom's `<dependency>` by the
+//! text of their key child elements.
+
+mod common;
+
+use common::{fixture, run_with_stdin, stderr, stdout, xml_pretty};
+
+/// The comments and artifact IDs in the formatted `pom`, in order.
+fn artifacts(pom: &str) -> Vec<&str> {
+    let lines = pom.lines().map(str::trim).collect::<Vec<_>>();
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| match *line {
+            "<artifactId>" => Some(lines[i + 1]),
+            line if line.starts_with("<!--") => Some(line),
+            _ => None,
+        })
+        .collect()
+}
+
+fn sorted(by: &str) -> String {
+    let output = xml_pretty()
+        .args(["--sort-children", "dependencies", "--sort-children-by", by])
+        .arg(fixture("pom.xml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    stdout(&output)
+}
+
+#[test]
+fn dependencies_sort_by_group_and_artifact() {
+    assert_eq!(
+        artifacts(&sorted("key=groupId/artifactId")),
+        [
+            "app",
+            "legacy",
+            "failureaccess",
+            "guava",
+            "junit",
+            "<!-- Logging -->",
+            "slf4j-api",
+            // Without a groupId, last and in their original order.
+            "<!-- Managed by the parent, so no groupId here -->",
+            "internal-bom",
+            "internal-tools",
+        ]
+    );
+}
+
+#[test]
+fn icase_ignores_the_case_of_the_keys() {
+    assert_eq!(
+        artifacts(&sorted("key=groupId/artifactId:icase")),
+        [
+            "app",
+            "failureaccess",
+            "guava",
+            "junit",
+            "legacy",
+            "<!-- Logging -->",
+            "slf4j-api",
+            "<!-- Managed by the parent, so no groupId here -->",
+            "internal-bom",
+            "internal-tools",
+        ]
+    );
+}
+
+#[test]
+fn sorting_is_stable_and_idempotent() {
+    let once = sorted("key=groupId/artifactId");
+    let output = run_with_stdin(
+        xml_pretty().args([
+            "--sort-children",
+            "dependencies",
+            "--sort-children-by",
+            "key=groupId/artifactId",
+            "--stdin",
+        ]),
+        &once,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(stdout(&output), once);
+}
@@ -0,0 +1,27 @@
+//! `--strip-attr`, which removes attributes by name, or by prefix with a trailing `*`.
+
+mod common;
+
+use common::format;
+
+const DOCUMENT: &str = "<r xmlns=\"urn:r\" xmlns:data-x=\"urn:x\" data-a=\"1\" id=\"2\" data-b-c=\"3\" data=\"4\"><s data-q=\"5\" t=\"6\"/></r>";
+
+#[test]
+fn matching_attributes_are_removed_from_every_element() {
+    assert_eq!(
+        format(&["--strip-attr", "data-*"], DOCUMENT).unwrap(),
+        "<r xmlns=\"urn:r\" xmlns:data-x=\"urn:x\" id=\"2\" data=\"4\">\n  <s t=\"6\" />\n</r>\n"
+    );
+    assert_eq!(
+        format(&["--strip-attr", "data-*", "--strip-attr", "id"], DOCUMENT).unwrap(),
+        "<r xmlns=\"urn:r\" xmlns:data-x=\"urn:x\" data=\"4\">\n  <s t=\"6\" />\n</r>\n"
+    );
+}
+
+#[test]
+fn namespace_declarations_are_never_removed() {
+    assert_eq!(
+        format(&["--strip-attr", "xmlns*", "--strip-attr", "*"], DOCUMENT).unwrap(),
+        "<r xmlns=\"urn:r\" xmlns:data-x=\"urn:x\">\n  <s/>\n</r>\n"
+    );
+}
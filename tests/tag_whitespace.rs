@@ -0,0 +1,44 @@
+//! Whitespace that XML allows inside tags is accepted, and formatted away.
+
+mod common;
+
+use common::{format, run_with_stdin, stderr, xml_pretty};
+
+const FORMATTED: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\n<root a=\"1\" b=\"2\" c=\"3\">\n  <e x=\"1\" />\n</root>\n";
+
+#[test]
+fn whitespace_between_and_around_attributes_is_accepted() {
+    let inputs = [
+        // Tabs, CR LF and bare LF between attributes, and spaces around `=`.
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?><root\ta=\"1\"\r\n  b = \"2\"\n\tc='3'\n><e x=\"1\"\n/></root\n>",
+        // The same in the declaration.
+        "<?xml version = \"1.0\"\n  encoding = 'UTF-8' ?>\n<root a=\"1\"\nb=\"2\"\tc=\"3\"><e\n\tx=\"1\" /></root>",
+    ];
+    for input in inputs {
+        let formatted = format(&[], input).unwrap();
+        assert_eq!(formatted, FORMATTED, "{:?}", input);
+    }
+
+    let output = run_with_stdin(xml_pretty().args(["check", "--stdin"]), FORMATTED);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
+
+#[test]
+fn whitespace_in_the_declaration_does_not_hide_the_encoding() {
+    assert_eq!(
+        format(
+            &["--fix-encoding-declaration"],
+            "<?xml version = \"1.0\"\n  encoding = 'ISO-8859-1' ?>\n<root/>"
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\n<root/>\n"
+    );
+    assert_eq!(
+        format(
+            &["--prolog-blank-line", "no"],
+            "<?xml version = \"1.0\"\n?>\n\n<root/>"
+        )
+        .unwrap(),
+        "<?xml version=\"1.0\"?>\n<root/>\n"
+    );
+}
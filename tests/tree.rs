@@ -0,0 +1,66 @@
+//! `tree` prints the path of each element, down to `--depth`, with `--attrs` its attributes.
+
+mod common;
+
+use common::{stderr, stdout, xml_pretty, TempDir};
+
+const LIBRARY: &str = r#"<library xmlns:x="urn:x">
+  <shelf id="a">
+    <book title="Say &quot;hi&quot;" note="tab&#9;line&#10;é\"/>
+    <x:book/>
+  </shelf>
+  <shelf id="b"><book><part/></book></shelf>
+</library>
+"#;
+
+fn tree(args: &[&str]) -> String {
+    let dir = TempDir::new("tree");
+    let document = dir.write("library.xml", LIBRARY);
+    let output = xml_pretty()
+        .arg("tree")
+        .args(args)
+        .arg(&document)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    stdout(&output)
+}
+
+#[test]
+fn lists_every_element_in_document_order() {
+    assert_eq!(
+        tree(&[]),
+        "/library\n\
+         /library/shelf\n\
+         /library/shelf/book\n\
+         /library/shelf/x:book\n\
+         /library/shelf\n\
+         /library/shelf/book\n\
+         /library/shelf/book/part\n"
+    );
+}
+
+#[test]
+fn depth_stops_below_the_given_level() {
+    assert_eq!(tree(&["--depth", "1"]), "/library\n");
+    assert_eq!(
+        tree(&["--depth", "2"]),
+        "/library\n/library/shelf\n/library/shelf\n"
+    );
+    assert_eq!(tree(&["--depth", "0"]), "");
+}
+
+#[test]
+fn attrs_shows_values_escaped_on_one_line() {
+    assert_eq!(
+        tree(&["--depth", "3", "--attrs"]),
+        concat!(
+            "/library xmlns:x=\"urn:x\"\n",
+            "/library/shelf id=\"a\"\n",
+            "/library/shelf/book title=\"Say \\\"hi\\\"\" note=\"tab\\tline\\né\\\\\"\n",
+            "/library/shelf/x:book\n",
+            "/library/shelf id=\"b\"\n",
+            "/library/shelf/book\n",
+        )
+    );
+}
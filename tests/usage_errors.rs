@@ -0,0 +1,118 @@
+//! Options that conflict, or that are missing one they need, are refused before any document
+//! is touched, with the same exit status as options that don't parse.
+
+mod common;
+
+use std::fs;
+
+use common::{stderr, xml_pretty, TempDir};
+
+const DOCUMENT: &str = "<r><a/></r>";
+
+#[test]
+fn conflicting_options_exit_with_status_2() {
+    let dir = TempDir::new("usage-errors");
+    let a = dir.write("a.xml", DOCUMENT);
+    let b = dir.write("b.xml", DOCUMENT);
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+    let out = dir.path("out.xml");
+    let out = out.to_str().unwrap();
+    let snaps = dir.path("snaps");
+    let snaps = snaps.to_str().unwrap();
+
+    let cases: &[(&[&str], &str)] = &[
+        (
+            &["--indent-style", "tab", a],
+            "--indent-style tab needs --reindent-only",
+        ),
+        (
+            &["--reindent-only", "--sort-attributes", a],
+            "--reindent-only leaves",
+        ),
+        (
+            &["--fix-default-namespace", a],
+            "--fix-default-namespace needs",
+        ),
+        (&["--end-pad", "70000", a], "--end-pad can be at most"),
+        (
+            &["--input-fd", "0", a],
+            "cannot read from --input-fd and from paths",
+        ),
+        (&["--stdin", a], "cannot read from --stdin and from paths"),
+        (
+            &["--output-fd", "1", "--replace", a],
+            "--output-fd cannot be combined",
+        ),
+        (
+            &["--stdin", "--replace"],
+            "cannot replace 'file' when provided stdin",
+        ),
+        (
+            &["-o", out, a, b],
+            "cannot write multiple documents to one output file",
+        ),
+        (
+            &["--diff-tool", "diff", a],
+            "--diff-tool only applies when linting",
+        ),
+        (
+            &["check", "--diff-tool", " ", a],
+            "--diff-tool needs a command",
+        ),
+        (
+            &["--comment-width", "40", a],
+            "--comment-width only applies",
+        ),
+        (&["--accept", a], "--accept needs --snapshot-dir"),
+        (
+            &["--snapshot-dir", snaps, "--replace", a],
+            "--snapshot-dir cannot be combined",
+        ),
+        (
+            &["--lint", "--snapshot-dir", snaps, a],
+            "--snapshot-dir compares documents itself",
+        ),
+        (
+            &["--snapshot-dir", snaps, "--stdin"],
+            "--snapshot-dir needs a file name",
+        ),
+    ];
+
+    for (args, message) in cases {
+        let output = xml_pretty().args(*args).output().unwrap();
+        assert_eq!(
+            output.status.code(),
+            Some(2),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+        assert!(
+            stderr(&output).starts_with(&format!("ERROR: {}", message)),
+            "{:?}: {}",
+            args,
+            stderr(&output)
+        );
+        assert!(output.stdout.is_empty(), "{:?}", args);
+    }
+
+    // Nothing was written.
+    assert_eq!(fs::read_to_string(a).unwrap(), DOCUMENT);
+    assert!(!dir.path("out.xml").exists());
+    assert!(!dir.path("snaps").exists());
+}
+
+#[test]
+fn unopenable_log_file_exits_with_status_1() {
+    let dir = TempDir::new("usage-errors-log");
+    let a = dir.write("a.xml", DOCUMENT);
+    let output = xml_pretty()
+        .arg("--log-file")
+        .arg(dir.path("missing/log.jsonl"))
+        .arg(&a)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(stderr(&output).starts_with("ERROR: cannot open log file"));
+    assert!(output.stdout.is_empty());
+}
@@ -0,0 +1,81 @@
+//! Smoke test of the WASI build: xml-pretty is built for `wasm32-wasip1` and run under wasmtime,
+//! and must format documents from stdin and from a preopened directory exactly as the native
+//! build does. Skipped unless both the target and wasmtime are installed.
+
+mod common;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use common::{fixture, format, run_with_stdin, stderr, xml_pretty};
+
+const TARGET: &str = "wasm32-wasip1";
+
+fn has_target() -> bool {
+    let Ok(output) = Command::new("rustc").args(["--print", "sysroot"]).output() else {
+        return false;
+    };
+    let sysroot = String::from_utf8_lossy(&output.stdout);
+    Path::new(sysroot.trim())
+        .join("lib/rustlib")
+        .join(TARGET)
+        .is_dir()
+}
+
+fn has_wasmtime() -> bool {
+    Command::new("wasmtime").arg("--version").output().is_ok()
+}
+
+/// Builds the WASI binary in a target directory of its own, so as not to wait on the build
+/// running the tests, and returns its path.
+fn build() -> PathBuf {
+    let target_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/wasi-smoke");
+    let output = Command::new(env!("CARGO"))
+        .args(["build", "--bin", "xml-pretty", "--target", TARGET])
+        .env("CARGO_TARGET_DIR", &target_dir)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    target_dir.join(TARGET).join("debug/xml-pretty.wasm")
+}
+
+#[test]
+fn wasi_build_formats_like_native() {
+    if !has_target() || !has_wasmtime() {
+        eprintln!(
+            "the {} target or wasmtime is not installed; skipping",
+            TARGET
+        );
+        return;
+    }
+    let wasm = build();
+    let input = "<root><a x=\"1\">text</a><!-- c --><b/></root>";
+
+    let native = format(&[], input).unwrap();
+    let output = run_with_stdin(
+        Command::new("wasmtime")
+            .arg("run")
+            .arg(&wasm)
+            .arg("--stdin"),
+        input,
+    );
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), native);
+
+    // Paths are reachable through the directory wasmtime preopens.
+    let dir = fixture("xmllint");
+    let native = xml_pretty().arg(dir.join("pom.xml")).output().unwrap();
+    let output = Command::new("wasmtime")
+        .arg("run")
+        .arg("--dir")
+        .arg(format!("{}::/fixtures", dir.display()))
+        .arg(&wasm)
+        .arg("/fixtures/pom.xml")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(output.stdout, native.stdout);
+}
@@ -0,0 +1,72 @@
+//! `--compat xmllint` against output recorded from `xmllint --format`. Each
+//! `tests/fixtures/xmllint/NAME.xml` has the output xmllint gave for it in `NAME.expected`.
+
+mod common;
+
+use std::{fs, path::PathBuf, process::Command};
+
+use common::{fixture, stderr, xml_pretty};
+
+fn fixtures() -> Vec<(PathBuf, PathBuf)> {
+    let mut inputs = fs::read_dir(fixture("xmllint"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "xml"))
+        .collect::<Vec<_>>();
+    inputs.sort();
+    assert!(!inputs.is_empty());
+    inputs
+        .into_iter()
+        .map(|input| {
+            let expected = input.with_extension("expected");
+            (input, expected)
+        })
+        .collect()
+}
+
+#[test]
+fn compat_xmllint_matches_recorded_output() {
+    let mut mismatches = vec![];
+
+    for (input, expected) in fixtures() {
+        let output = xml_pretty()
+            .args(["--compat", "xmllint"])
+            .arg(&input)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+        if output.stdout != fs::read(&expected).unwrap() {
+            mismatches.push(format!(
+                "{}:\n{}",
+                input.display(),
+                String::from_utf8_lossy(&output.stdout)
+            ));
+        }
+    }
+
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
+
+/// Keeps the recorded output honest: where xmllint is installed, it must still give it.
+#[test]
+fn recorded_output_is_xmllints() {
+    if Command::new("xmllint").arg("--version").output().is_err() {
+        eprintln!("xmllint is not installed; not re-recording its output");
+        return;
+    }
+
+    for (input, expected) in fixtures() {
+        let output = Command::new("xmllint")
+            .arg("--format")
+            .arg(&input)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(
+            String::from_utf8_lossy(&output.stdout),
+            fs::read_to_string(&expected).unwrap(),
+            "{}",
+            input.display()
+        );
+    }
+}
@@ -0,0 +1,52 @@
+//! `--xmlns-sort`, which orders the namespace declarations that `--sort-attributes` puts first.
+
+mod common;
+
+use common::{format, stderr, xml_pretty};
+
+/// Prefix `a` is bound to the last URI alphabetically, and `b` to the first.
+const DOCUMENT: &str = "<r xmlns:b=\"urn:a\" c=\"1\" xmlns:a=\"urn:z\" xmlns=\"urn:d\" a=\"2\"/>";
+
+#[test]
+fn declarations_sort_by_prefix_uri_or_not_at_all() {
+    let sorted = |xmlns_sort: &[&str]| {
+        let mut args = vec!["--sort-attributes"];
+        args.extend(xmlns_sort);
+        format(&args, DOCUMENT).unwrap()
+    };
+
+    let by_prefix = "<r xmlns=\"urn:d\" xmlns:a=\"urn:z\" xmlns:b=\"urn:a\" a=\"2\" c=\"1\" />\n";
+    assert_eq!(sorted(&[]), by_prefix);
+    assert_eq!(sorted(&["--xmlns-sort", "prefix"]), by_prefix);
+
+    // The default namespace stays first, and the other attributes are sorted as before.
+    let in_input_order =
+        "<r xmlns=\"urn:d\" xmlns:b=\"urn:a\" xmlns:a=\"urn:z\" a=\"2\" c=\"1\" />\n";
+    assert_eq!(sorted(&["--xmlns-sort", "uri"]), in_input_order);
+    assert_eq!(sorted(&["--xmlns-sort", "none"]), in_input_order);
+
+    // In prefix order to begin with, which `none` keeps and `uri` reverses.
+    let document = "<r xmlns:a=\"urn:z\" xmlns:b=\"urn:a\"/>";
+    assert_eq!(
+        format(&["--sort-attributes", "--xmlns-sort", "uri"], document).unwrap(),
+        "<r xmlns:b=\"urn:a\" xmlns:a=\"urn:z\" />\n"
+    );
+    assert_eq!(
+        format(&["--sort-attributes", "--xmlns-sort", "none"], document).unwrap(),
+        "<r xmlns:a=\"urn:z\" xmlns:b=\"urn:a\" />\n"
+    );
+}
+
+#[test]
+fn unknown_orders_are_refused() {
+    let output = xml_pretty()
+        .args(["--sort-attributes", "--xmlns-sort", "url", "--stdin"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("expected 'prefix', 'uri' or 'none', got 'url'"),
+        "{}",
+        stderr(&output)
+    );
+}